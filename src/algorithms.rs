@@ -1,7 +1,34 @@
 use std::f32::consts::PI;
+use std::f64::consts::{FRAC_2_PI, FRAC_PI_2};
+use std::sync::OnceLock;
 
 use nih_plug::prelude::Enum;
 
+use self::custom::SampledCurve;
+use self::fold::FoldSettings;
+use crate::dsp::analog::AnalogCoefficients;
+use crate::dsp::crossfade::MAX_FADE_TERMS;
+
+pub mod custom;
+pub mod fold;
+
+/// Where `1.5x - 0.7x^3` crosses -1, after which the chomper stays clipped.
+const CHOMPER_KNEE: f64 = 1.7238266174072208;
+/// Where `0.14x^5 - 1.15x^3 + 1.9x` crosses 1, after which the humpback stays clipped.
+const HUMPBACK_KNEE: f64 = 2.5638924278586037;
+/// Where the tube's positive half levels off. It's lower than the negative half's, as the grid
+/// starts conducting well before the tube cuts off.
+const TUBE_POSITIVE_LIMIT: f64 = 0.8;
+const TUBE_NEGATIVE_LIMIT: f64 = 1.0;
+
+/// The tabulated antiderivatives cover inputs up to this value.
+const TABLE_RANGE: usize = 16;
+/// Table entries per unit of input.
+const TABLE_RESOLUTION: usize = 512;
+
+static RADIAL_TABLE: OnceLock<AntiderivativeTable> = OnceLock::new();
+static STEPPER_TABLE: OnceLock<AntiderivativeTable> = OnceLock::new();
+
 #[derive(PartialEq, Enum, Copy, Clone)]
 pub enum DistortionAlgorithm {
     SoftClip,
@@ -12,10 +39,38 @@ pub enum DistortionAlgorithm {
     Stepper,
     Humpback,
     Absolute,
+    /// The user-drawn curve from [`AlgorithmContext::custom`].
+    Custom,
+    #[name = "Triangle Fold"]
+    TriangleFold,
+    #[name = "Sine Fold"]
+    SineFold,
+    /// Folds several times with unevenly sized folds.
+    #[name = "Buchla Fold"]
+    BuchlaFold,
+    /// A triode whose bias shifts as grid current flows. See [`crate::dsp::analog::Tube`].
+    Tube,
+    /// Magnetic tape with hysteresis. See [`crate::dsp::analog::Tape`].
+    Tape,
+}
+
+/// The state some algorithms need besides their input.
+#[derive(Clone, Copy)]
+pub struct AlgorithmContext<'a> {
+    pub custom: &'a SampledCurve,
+    pub fold: FoldSettings,
+    pub analog: &'a AnalogCoefficients,
 }
 
 impl DistortionAlgorithm {
-    pub fn calculate(self, x: f32) -> f32 {
+    /// Whether the algorithm's output depends on earlier samples. Those algorithms are processed by
+    /// [`Shaper`][crate::dsp::shaper::Shaper] itself, and [`calculate()`][Self::calculate()] only
+    /// gives their static curve.
+    pub fn is_stateful(self) -> bool {
+        matches!(self, DistortionAlgorithm::Tube | DistortionAlgorithm::Tape)
+    }
+
+    pub fn calculate(self, x: f32, context: AlgorithmContext) -> f32 {
         match self {
             DistortionAlgorithm::SoftClip => soft_clip(x),
             DistortionAlgorithm::HardClip => hard_clip(x),
@@ -25,8 +80,187 @@ impl DistortionAlgorithm {
             DistortionAlgorithm::Stepper => stepper(x),
             DistortionAlgorithm::Humpback => humpback(x),
             DistortionAlgorithm::Absolute => absolute(x),
+            DistortionAlgorithm::Custom => context.custom.calculate(x),
+            DistortionAlgorithm::TriangleFold => fold::TRIANGLE.calculate(x, context.fold),
+            DistortionAlgorithm::SineFold => fold::SINE.calculate(x, context.fold),
+            DistortionAlgorithm::BuchlaFold => fold::BUCHLA.calculate(x, context.fold),
+            DistortionAlgorithm::Tube => tube(x),
+            DistortionAlgorithm::Tape => tape(x),
+        }
+    }
+
+    /// The first antiderivative of [`calculate()`][Self::calculate()], used for anti-aliasing.
+    pub fn antiderivative(self, x: f64, context: AlgorithmContext) -> f64 {
+        match self {
+            DistortionAlgorithm::SoftClip => soft_clip_antiderivative(x),
+            DistortionAlgorithm::HardClip => hard_clip_antiderivative(x),
+            DistortionAlgorithm::Radial => radial_table().antiderivative(x),
+            DistortionAlgorithm::Chomper => chomper_antiderivative(x),
+            DistortionAlgorithm::Sine => sine_antiderivative(x),
+            DistortionAlgorithm::Stepper => stepper_table().antiderivative(x),
+            DistortionAlgorithm::Humpback => humpback_antiderivative(x),
+            DistortionAlgorithm::Absolute => absolute_antiderivative(x),
+            DistortionAlgorithm::Custom => context.custom.antiderivative(x),
+            DistortionAlgorithm::TriangleFold => fold::TRIANGLE.antiderivative(x, context.fold),
+            DistortionAlgorithm::SineFold => fold::SINE.antiderivative(x, context.fold),
+            DistortionAlgorithm::BuchlaFold => fold::BUCHLA.antiderivative(x, context.fold),
+            // The shaper runs these itself and never anti-aliases them
+            DistortionAlgorithm::Tube | DistortionAlgorithm::Tape => 0.0,
+        }
+    }
+
+    /// The second antiderivative of [`calculate()`][Self::calculate()], used for anti-aliasing.
+    pub fn second_antiderivative(self, x: f64, context: AlgorithmContext) -> f64 {
+        match self {
+            DistortionAlgorithm::SoftClip => soft_clip_second_antiderivative(x),
+            DistortionAlgorithm::HardClip => hard_clip_second_antiderivative(x),
+            DistortionAlgorithm::Radial => radial_table().second_antiderivative(x),
+            DistortionAlgorithm::Chomper => chomper_second_antiderivative(x),
+            DistortionAlgorithm::Sine => sine_second_antiderivative(x),
+            DistortionAlgorithm::Stepper => stepper_table().second_antiderivative(x),
+            DistortionAlgorithm::Humpback => humpback_second_antiderivative(x),
+            DistortionAlgorithm::Absolute => absolute_second_antiderivative(x),
+            DistortionAlgorithm::Custom => context.custom.second_antiderivative(x),
+            DistortionAlgorithm::TriangleFold => {
+                fold::TRIANGLE.second_antiderivative(x, context.fold)
+            }
+            DistortionAlgorithm::SineFold => fold::SINE.second_antiderivative(x, context.fold),
+            DistortionAlgorithm::BuchlaFold => fold::BUCHLA.second_antiderivative(x, context.fold),
+            DistortionAlgorithm::Tube | DistortionAlgorithm::Tape => 0.0,
+        }
+    }
+}
+
+/// The most algorithms a [`Blend`] can combine, which is enough for morphing between two
+/// algorithms that are both crossfading.
+const MAX_BLEND_TERMS: usize = 2 * MAX_FADE_TERMS;
+
+/// A weighted sum of algorithms. Taking antiderivatives is linear, so a blend can be anti-aliased
+/// just like a single algorithm.
+#[derive(Clone, Copy)]
+pub struct Blend {
+    terms: [(DistortionAlgorithm, f32); MAX_BLEND_TERMS],
+    len: usize,
+}
+
+impl From<DistortionAlgorithm> for Blend {
+    fn from(algorithm: DistortionAlgorithm) -> Self {
+        let mut terms = [(algorithm, 0.0); MAX_BLEND_TERMS];
+        terms[0].1 = 1.0;
+        Self { terms, len: 1 }
+    }
+}
+
+impl Blend {
+    /// Crossfades linearly from `a` to `b` as `amount` goes from zero to one.
+    pub fn morph(a: Blend, b: Blend, amount: f32) -> Self {
+        Self::weighted(a, 1.0 - amount, b, amount)
+    }
+
+    /// Combines two blends, scaling each by its weight. Terms that end up with a weight of zero are
+    /// dropped.
+    pub fn weighted(a: Blend, weight_a: f32, b: Blend, weight_b: f32) -> Self {
+        let mut blend = Self {
+            terms: [(DistortionAlgorithm::SoftClip, 0.0); MAX_BLEND_TERMS],
+            len: 0,
+        };
+        let scaled_a = a
+            .terms()
+            .iter()
+            .map(|&(algorithm, w)| (algorithm, w * weight_a));
+        let scaled_b = b
+            .terms()
+            .iter()
+            .map(|&(algorithm, w)| (algorithm, w * weight_b));
+        for (algorithm, weight) in scaled_a.chain(scaled_b).filter(|(_, w)| *w != 0.0) {
+            // Blending an algorithm with itself doesn't need another term
+            if let Some(term) = blend.terms[..blend.len]
+                .iter_mut()
+                .find(|(existing, _)| *existing == algorithm)
+            {
+                term.1 += weight;
+            } else {
+                blend.terms[blend.len] = (algorithm, weight);
+                blend.len += 1;
+            }
+        }
+        blend
+    }
+
+    fn terms(&self) -> &[(DistortionAlgorithm, f32)] {
+        &self.terms[..self.len]
+    }
+
+    /// Drops the terms whose weight is below `threshold`, and then the quietest of the rest until
+    /// at most `max_terms` are left.
+    pub fn without_quietest(&self, threshold: f32, max_terms: usize) -> Self {
+        let mut blend = self.filter(|(_, weight)| weight.abs() >= threshold);
+        while blend.len > max_terms {
+            let quietest = (0..blend.len)
+                .min_by(|&a, &b| blend.terms[a].1.abs().total_cmp(&blend.terms[b].1.abs()))
+                .unwrap_or(0);
+            blend.terms.copy_within(quietest + 1..blend.len, quietest);
+            blend.len -= 1;
         }
+        blend
     }
+
+    /// The weight of a single algorithm in the blend.
+    pub fn weight(&self, algorithm: DistortionAlgorithm) -> f32 {
+        self.terms()
+            .iter()
+            .filter(|(existing, _)| *existing == algorithm)
+            .map(|(_, weight)| weight)
+            .sum()
+    }
+
+    /// The blend without a single algorithm.
+    pub fn without(&self, algorithm: DistortionAlgorithm) -> Self {
+        self.filter(|(existing, _)| *existing != algorithm)
+    }
+
+    /// The blend without its stateful algorithms.
+    pub fn stateless(&self) -> Self {
+        self.filter(|(algorithm, _)| !algorithm.is_stateful())
+    }
+
+    fn filter(&self, keep: impl Fn(&(DistortionAlgorithm, f32)) -> bool) -> Self {
+        let mut blend = *self;
+        blend.len = 0;
+        for &term in self.terms().iter().filter(|term| keep(term)) {
+            blend.terms[blend.len] = term;
+            blend.len += 1;
+        }
+        blend
+    }
+
+    pub fn calculate(&self, x: f32, context: AlgorithmContext) -> f32 {
+        self.terms()
+            .iter()
+            .map(|&(algorithm, weight)| weight * algorithm.calculate(x, context))
+            .sum()
+    }
+
+    pub fn antiderivative(&self, x: f64, context: AlgorithmContext) -> f64 {
+        self.terms()
+            .iter()
+            .map(|&(algorithm, weight)| weight as f64 * algorithm.antiderivative(x, context))
+            .sum()
+    }
+
+    pub fn second_antiderivative(&self, x: f64, context: AlgorithmContext) -> f64 {
+        self.terms()
+            .iter()
+            .map(|&(algorithm, weight)| weight as f64 * algorithm.second_antiderivative(x, context))
+            .sum()
+    }
+}
+
+/// Builds the tables for the algorithms without closed-form antiderivatives. This allocates, so it
+/// should be called before processing starts.
+pub fn prepare_antiderivative_tables() {
+    radial_table();
+    stepper_table();
 }
 
 impl From<usize> for DistortionAlgorithm {
@@ -48,6 +282,8 @@ pub fn soft_clip(mut x: f32) -> f32 {
 }
 
 #[inline]
+// Unlike `clamp()` this clips NaN to -1 instead of passing it on
+#[allow(clippy::manual_clamp)]
 pub fn hard_clip(x: f32) -> f32 {
     x.max(-1.0).min(1.0)
 }
@@ -85,3 +321,257 @@ pub fn humpback(x: f32) -> f32 {
 pub fn absolute(x: f32) -> f32 {
     hard_clip(x.abs())
 }
+
+/// An asymmetric exponential saturation, leveling off sooner for positive inputs.
+#[inline]
+pub fn tube(x: f32) -> f32 {
+    let x = x as f64;
+    let y = if x >= 0.0 {
+        exponential_saturation(x, TUBE_POSITIVE_LIMIT)
+    } else {
+        -exponential_saturation(-x, TUBE_NEGATIVE_LIMIT)
+    };
+    y as f32
+}
+
+/// The anhysteretic magnetization curve of tape, a Langevin function scaled to a slope of one.
+#[inline]
+pub fn tape(x: f32) -> f32 {
+    let u = 3.0 * x as f64;
+    if u.abs() < 1e-3 {
+        // Avoids cancellation near zero
+        return x;
+    }
+    (1.0 / u.tanh() - 1.0 / u) as f32
+}
+
+#[inline]
+pub fn soft_clip_antiderivative(x: f64) -> f64 {
+    clipped_antiderivative(x, 1.0, 1.0, soft_clip_polynomial_antiderivative)
+}
+
+#[inline]
+pub fn soft_clip_second_antiderivative(x: f64) -> f64 {
+    clipped_second_antiderivative(x, 1.0, 1.0, soft_clip_polynomial_antiderivative, |x| {
+        0.25 * x.powi(3) - 0.025 * x.powi(5)
+    })
+}
+
+fn soft_clip_polynomial_antiderivative(x: f64) -> f64 {
+    0.75 * x.powi(2) - 0.125 * x.powi(4)
+}
+
+#[inline]
+pub fn hard_clip_antiderivative(x: f64) -> f64 {
+    clipped_antiderivative(x, 1.0, 1.0, hard_clip_linear_antiderivative)
+}
+
+#[inline]
+pub fn hard_clip_second_antiderivative(x: f64) -> f64 {
+    clipped_second_antiderivative(x, 1.0, 1.0, hard_clip_linear_antiderivative, |x| {
+        x.powi(3) / 6.0
+    })
+}
+
+fn hard_clip_linear_antiderivative(x: f64) -> f64 {
+    0.5 * x.powi(2)
+}
+
+#[inline]
+pub fn chomper_antiderivative(x: f64) -> f64 {
+    clipped_antiderivative(x, CHOMPER_KNEE, -1.0, chomper_polynomial_antiderivative)
+}
+
+#[inline]
+pub fn chomper_second_antiderivative(x: f64) -> f64 {
+    clipped_second_antiderivative(
+        x,
+        CHOMPER_KNEE,
+        -1.0,
+        chomper_polynomial_antiderivative,
+        |x| 0.25 * x.powi(3) - 0.035 * x.powi(5),
+    )
+}
+
+fn chomper_polynomial_antiderivative(x: f64) -> f64 {
+    0.75 * x.powi(2) - 0.175 * x.powi(4)
+}
+
+#[inline]
+pub fn sine_antiderivative(x: f64) -> f64 {
+    -FRAC_2_PI * (FRAC_PI_2 * x).cos()
+}
+
+#[inline]
+pub fn sine_second_antiderivative(x: f64) -> f64 {
+    -FRAC_2_PI.powi(2) * (FRAC_PI_2 * x).sin()
+}
+
+#[inline]
+pub fn humpback_antiderivative(x: f64) -> f64 {
+    clipped_antiderivative(x, HUMPBACK_KNEE, 1.0, humpback_polynomial_antiderivative)
+}
+
+#[inline]
+pub fn humpback_second_antiderivative(x: f64) -> f64 {
+    clipped_second_antiderivative(
+        x,
+        HUMPBACK_KNEE,
+        1.0,
+        humpback_polynomial_antiderivative,
+        |x| 0.14 / 42.0 * x.powi(7) - 1.15 / 20.0 * x.powi(5) + 0.95 / 3.0 * x.powi(3),
+    )
+}
+
+fn humpback_polynomial_antiderivative(x: f64) -> f64 {
+    0.14 / 6.0 * x.powi(6) - 1.15 / 4.0 * x.powi(4) + 0.95 * x.powi(2)
+}
+
+#[inline]
+pub fn absolute_antiderivative(x: f64) -> f64 {
+    let y = x.abs();
+    let value = if y <= 1.0 { 0.5 * y * y } else { y - 0.5 };
+    value * x.signum()
+}
+
+#[inline]
+pub fn absolute_second_antiderivative(x: f64) -> f64 {
+    let y = x.abs();
+    if y <= 1.0 {
+        y.powi(3) / 6.0
+    } else {
+        let d = y - 1.0;
+        1.0 / 6.0 + 0.5 * d + 0.5 * d * d
+    }
+}
+
+/// Rises with a slope of one at zero and levels off at `limit`.
+fn exponential_saturation(x: f64, limit: f64) -> f64 {
+    limit * (1.0 - (-x / limit).exp())
+}
+
+/// The first antiderivative of an odd function that follows a curve with the (even) antiderivative
+/// `inner` up to `knee`, and stays at `tail` past it.
+#[inline]
+fn clipped_antiderivative(x: f64, knee: f64, tail: f64, inner: fn(f64) -> f64) -> f64 {
+    let y = x.abs();
+    if y <= knee {
+        inner(y)
+    } else {
+        inner(knee) + tail * (y - knee)
+    }
+}
+
+/// The second antiderivative matching [`clipped_antiderivative()`], where `inner_second` is the
+/// (odd) antiderivative of `inner`.
+#[inline]
+fn clipped_second_antiderivative(
+    x: f64,
+    knee: f64,
+    tail: f64,
+    inner: fn(f64) -> f64,
+    inner_second: fn(f64) -> f64,
+) -> f64 {
+    let y = x.abs();
+    let value = if y <= knee {
+        inner_second(y)
+    } else {
+        let d = y - knee;
+        inner_second(knee) + inner(knee) * d + 0.5 * tail * d * d
+    };
+    value * x.signum()
+}
+
+fn radial_table() -> &'static AntiderivativeTable {
+    RADIAL_TABLE.get_or_init(|| AntiderivativeTable::new(|x| radial(x as f32) as f64))
+}
+
+fn stepper_table() -> &'static AntiderivativeTable {
+    STEPPER_TABLE.get_or_init(|| AntiderivativeTable::new(|x| stepper(x as f32) as f64))
+}
+
+/// Numerically integrated antiderivatives for odd functions without a usable closed form.
+struct AntiderivativeTable {
+    values: Vec<f64>,
+    first: Vec<f64>,
+    second: Vec<f64>,
+    /// The average slope of the first antiderivative over the last unit of the table, used to
+    /// extrapolate past its end.
+    tail_slope: f64,
+}
+
+impl AntiderivativeTable {
+    fn new(f: impl Fn(f64) -> f64) -> Self {
+        let len = TABLE_RANGE * TABLE_RESOLUTION + 1;
+        let h = 1.0 / TABLE_RESOLUTION as f64;
+        let values: Vec<f64> = (0..len).map(|i| f(i as f64 * h)).collect();
+        let mut first = vec![0.0; len];
+        let mut second = vec![0.0; len];
+
+        // Simpson's rule over both halves of every step, so the midpoint of the first
+        // antiderivative is available for integrating the second one
+        for i in 0..len - 1 {
+            let x = i as f64 * h;
+            let quarter = f(x + 0.25 * h);
+            let half = f(x + 0.5 * h);
+            let three_quarters = f(x + 0.75 * h);
+            let first_half = first[i] + h / 12.0 * (values[i] + 4.0 * quarter + half);
+            first[i + 1] = first_half + h / 12.0 * (half + 4.0 * three_quarters + values[i + 1]);
+            second[i + 1] = second[i] + h / 6.0 * (first[i] + 4.0 * first_half + first[i + 1]);
+        }
+
+        let tail_slope = first[len - 1] - first[len - 1 - TABLE_RESOLUTION];
+        Self {
+            values,
+            first,
+            second,
+            tail_slope,
+        }
+    }
+
+    fn antiderivative(&self, x: f64) -> f64 {
+        let y = x.abs();
+        match self.interval(y) {
+            Some((i, t)) => self.interpolate(&self.first, &self.values, i, t),
+            None => {
+                let end = self.first.len() - 1;
+                self.first[end] + self.tail_slope * (y - TABLE_RANGE as f64)
+            }
+        }
+    }
+
+    fn second_antiderivative(&self, x: f64) -> f64 {
+        let y = x.abs();
+        let value = match self.interval(y) {
+            Some((i, t)) => self.interpolate(&self.second, &self.first, i, t),
+            None => {
+                let end = self.second.len() - 1;
+                let d = y - TABLE_RANGE as f64;
+                self.second[end] + self.first[end] * d + 0.5 * self.tail_slope * d * d
+            }
+        };
+        value * x.signum()
+    }
+
+    /// The index and position within the table step containing `y`, if it's inside the table.
+    fn interval(&self, y: f64) -> Option<(usize, f64)> {
+        let position = y * TABLE_RESOLUTION as f64;
+        let i = position as usize;
+        if i < self.values.len() - 1 {
+            Some((i, position - i as f64))
+        } else {
+            None
+        }
+    }
+
+    /// Cubic Hermite interpolation, using the tabulated derivatives as the tangents.
+    fn interpolate(&self, table: &[f64], derivative: &[f64], i: usize, t: f64) -> f64 {
+        let h = 1.0 / TABLE_RESOLUTION as f64;
+        let t2 = t * t;
+        let t3 = t2 * t;
+        (2.0 * t3 - 3.0 * t2 + 1.0) * table[i]
+            + (t3 - 2.0 * t2 + t) * h * derivative[i]
+            + (-2.0 * t3 + 3.0 * t2) * table[i + 1]
+            + (t3 - t2) * h * derivative[i + 1]
+    }
+}
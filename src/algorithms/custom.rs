@@ -0,0 +1,495 @@
+use atomic_float::{AtomicF32, AtomicF64};
+use serde::{Deserialize, Serialize};
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// The number of linear segments the table splits the curve's `[-1, 1]` input range into.
+const TABLE_SEGMENTS: usize = 1024;
+const SEGMENT_WIDTH: f64 = 2.0 / TABLE_SEGMENTS as f64;
+/// The closest two control points can get to each other horizontally.
+const MIN_POINT_DISTANCE: f32 = 0.01;
+
+/// A user-drawn transfer curve. The control points are connected by a monotone cubic spline, so the
+/// curve never overshoots them. Outside of `[-1, 1]` the curve stays at its end values.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "UncheckedCurve")]
+pub struct CustomCurve {
+    /// Sorted by input, and always ending at an input of 1. Asymmetric curves also start at -1,
+    /// while symmetric curves only store the points above zero.
+    points: Vec<(f32, f32)>,
+    /// Whether the curve is mirrored around the origin, which only produces odd harmonics.
+    symmetric: bool,
+}
+
+impl Default for CustomCurve {
+    fn default() -> Self {
+        Self {
+            points: vec![(0.4, 0.6), (1.0, 0.9)],
+            symmetric: true,
+        }
+    }
+}
+
+/// A curve as it was saved, which could be anything.
+#[derive(Deserialize)]
+struct UncheckedCurve {
+    points: Vec<(f32, f32)>,
+    symmetric: bool,
+}
+
+impl From<UncheckedCurve> for CustomCurve {
+    /// Restores the invariants [`CustomCurve::points`] relies on, falling back to the default curve
+    /// when there's too little left for a spline.
+    fn from(
+        UncheckedCurve {
+            mut points,
+            symmetric,
+        }: UncheckedCurve,
+    ) -> Self {
+        // Symmetric curves get the point at the origin for free
+        let min_x = if symmetric { 0.0 } else { -1.0 };
+        points.retain(|&(x, y)| x.is_finite() && y.is_finite() && (x > min_x || !symmetric));
+        for (x, y) in &mut points {
+            *x = x.clamp(min_x, 1.0);
+            *y = y.clamp(-1.0, 1.0);
+        }
+        points.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        points.dedup_by(|(a, _), (b, _)| a == b);
+
+        let min_points = if symmetric { 1 } else { 2 };
+        if points.len() < min_points {
+            return Self::default();
+        }
+        // The points are sorted, so moving the outer ones outwards keeps them that way
+        if let Some(last) = points.last_mut() {
+            last.0 = 1.0;
+        }
+        if !symmetric {
+            points[0].0 = -1.0;
+        }
+        Self { points, symmetric }
+    }
+}
+
+impl CustomCurve {
+    pub fn symmetric(&self) -> bool {
+        self.symmetric
+    }
+
+    pub fn set_symmetric(&mut self, symmetric: bool) {
+        if symmetric == self.symmetric {
+            return;
+        }
+        if symmetric {
+            self.points.retain(|&(x, _)| x > 0.0);
+        } else {
+            // Keep the curve exactly as it sounds right now
+            self.points = self.control_points();
+        }
+        self.symmetric = symmetric;
+    }
+
+    /// The points that can be edited. For symmetric curves these are only the ones above zero.
+    pub fn points(&self) -> &[(f32, f32)] {
+        &self.points
+    }
+
+    /// Maps a point to the part of the curve that is stored. Symmetric curves mirror points below
+    /// zero.
+    pub fn editable_point(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        if self.symmetric && x < 0.0 {
+            (-x, -y)
+        } else {
+            (x, y)
+        }
+    }
+
+    /// The end points can only be moved vertically, and they can't be removed.
+    pub fn is_end_point(&self, index: usize) -> bool {
+        index == self.points.len() - 1 || (!self.symmetric && index == 0)
+    }
+
+    /// Adds a control point, returning its index. Returns `None` if the point would be too close to
+    /// another one.
+    pub fn insert(&mut self, point: (f32, f32)) -> Option<usize> {
+        let (x, y) = self.editable_point(point);
+        let index = self.points.partition_point(|&(point_x, _)| point_x < x);
+        let (min, max) = self.horizontal_range(index, index);
+        if x < min || x > max {
+            return None;
+        }
+        self.points.insert(index, (x, y.clamp(-1.0, 1.0)));
+        Some(index)
+    }
+
+    /// Moves a control point, keeping it between its neighbours.
+    pub fn move_point(&mut self, index: usize, point: (f32, f32)) {
+        let (x, y) = self.editable_point(point);
+        let x = if self.is_end_point(index) {
+            self.points[index].0
+        } else {
+            let (min, max) = self.horizontal_range(index, index + 1);
+            x.clamp(min, max)
+        };
+        self.points[index] = (x, y.clamp(-1.0, 1.0));
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if !self.is_end_point(index) {
+            self.points.remove(index);
+        }
+    }
+
+    /// The range a point between `points[lower - 1]` and `points[upper]` can be placed in.
+    fn horizontal_range(&self, lower: usize, upper: usize) -> (f32, f32) {
+        let min = match lower.checked_sub(1) {
+            Some(previous) => self.points[previous].0,
+            None if self.symmetric => 0.0,
+            None => -1.0,
+        };
+        let max = self.points.get(upper).map_or(1.0, |&(x, _)| x);
+        (min + MIN_POINT_DISTANCE, max - MIN_POINT_DISTANCE)
+    }
+
+    /// All points the spline goes through, including the mirrored ones for symmetric curves.
+    pub fn control_points(&self) -> Vec<(f32, f32)> {
+        if !self.symmetric {
+            return self.points.clone();
+        }
+        self.points
+            .iter()
+            .rev()
+            .map(|&(x, y)| (-x, -y))
+            .chain([(0.0, 0.0)])
+            .chain(self.points.iter().copied())
+            .collect()
+    }
+}
+
+/// A monotone cubic Hermite spline using the Fritsch-Carlson tangents.
+struct Spline<'a> {
+    points: &'a [(f32, f32)],
+    tangents: Vec<f32>,
+}
+
+impl<'a> Spline<'a> {
+    fn new(points: &'a [(f32, f32)]) -> Self {
+        let secants: Vec<f32> = points
+            .windows(2)
+            .map(|pair| (pair[1].1 - pair[0].1) / (pair[1].0 - pair[0].0))
+            .collect();
+        let mut tangents: Vec<f32> = (0..points.len())
+            .map(
+                |i| match (i.checked_sub(1).map(|j| secants[j]), secants.get(i)) {
+                    (Some(left), Some(&right)) if left * right > 0.0 => (left + right) / 2.0,
+                    (Some(_), Some(_)) => 0.0,
+                    (Some(left), None) => left,
+                    (None, Some(&right)) => right,
+                    (None, None) => 0.0,
+                },
+            )
+            .collect();
+
+        // Limits the tangents so the segments stay monotone
+        for (i, &secant) in secants.iter().enumerate() {
+            if secant == 0.0 {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+                continue;
+            }
+            let alpha = tangents[i] / secant;
+            let beta = tangents[i + 1] / secant;
+            let length = alpha.hypot(beta);
+            if length > 3.0 {
+                let tau = 3.0 / length;
+                tangents[i] = tau * alpha * secant;
+                tangents[i + 1] = tau * beta * secant;
+            }
+        }
+
+        Self { points, tangents }
+    }
+
+    fn evaluate(&self, x: f32) -> f32 {
+        let (first, last) = (self.points[0], self.points[self.points.len() - 1]);
+        if x <= first.0 {
+            return first.1;
+        }
+        if x >= last.0 {
+            return last.1;
+        }
+
+        let i = self.points.partition_point(|&(point_x, _)| point_x <= x) - 1;
+        let ((x0, y0), (x1, y1)) = (self.points[i], self.points[i + 1]);
+        let h = x1 - x0;
+        let t = (x - x0) / h;
+        let t2 = t * t;
+        let t3 = t2 * t;
+        (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+            + (t3 - 2.0 * t2 + t) * h * self.tangents[i]
+            + (-2.0 * t3 + 3.0 * t2) * y1
+            + (t3 - t2) * h * self.tangents[i + 1]
+    }
+}
+
+/// Two copies of a [`SampledCurve`], so the editor can rewrite one while the other one is being
+/// read. The editor writes to the copy nobody is reading and then swaps them, so readers always see
+/// a whole table, never half of an old one and half of a new one.
+pub struct CustomTable {
+    tables: [SampledCurve; 2],
+    /// The table new readers get.
+    active: AtomicUsize,
+    /// The number of readers holding on to each table.
+    readers: [AtomicUsize; 2],
+    /// Only one update can write to the inactive table at a time.
+    writer: Mutex<()>,
+}
+
+impl Default for CustomTable {
+    fn default() -> Self {
+        let table = Self {
+            tables: Default::default(),
+            active: AtomicUsize::new(0),
+            readers: Default::default(),
+            writer: Mutex::new(()),
+        };
+        table.update(&CustomCurve::default());
+        table
+    }
+}
+
+impl CustomTable {
+    /// Resamples the curve into the inactive table and makes that the active one. This allocates
+    /// and can wait for a reader to let go of the inactive table, so it shouldn't be called from the
+    /// audio thread.
+    pub fn update(&self, curve: &CustomCurve) {
+        let _writer = self.writer.lock().unwrap();
+        let inactive = 1 - self.active.load(Ordering::SeqCst);
+        // A reader may still be holding on to the table from before the last update
+        while self.readers[inactive].load(Ordering::SeqCst) > 0 {
+            std::thread::yield_now();
+        }
+        self.tables[inactive].update(curve);
+        self.active.store(inactive, Ordering::SeqCst);
+    }
+
+    /// The current table, which stays the same until the guard is dropped. This never waits, so
+    /// it's safe to call from the audio thread.
+    pub fn read(&self) -> CustomTableGuard<'_> {
+        loop {
+            let index = self.active.load(Ordering::SeqCst);
+            self.readers[index].fetch_add(1, Ordering::SeqCst);
+            // If an update swapped the tables in the meantime, the writer may already be
+            // rewriting this one
+            if self.active.load(Ordering::SeqCst) == index {
+                return CustomTableGuard { table: self, index };
+            }
+            self.readers[index].fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Keeps a [`CustomTable`]'s table from being rewritten while it's being read.
+pub struct CustomTableGuard<'a> {
+    table: &'a CustomTable,
+    index: usize,
+}
+
+impl Deref for CustomTableGuard<'_> {
+    type Target = SampledCurve;
+
+    fn deref(&self) -> &SampledCurve {
+        &self.table.tables[self.index]
+    }
+}
+
+impl Drop for CustomTableGuard<'_> {
+    fn drop(&mut self) {
+        self.table.readers[self.index].fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// A sampled copy of a [`CustomCurve`] along with its antiderivatives, so it can be used for
+/// anti-aliasing. The values are atomics so the table can be shared between threads, but
+/// [`CustomTable`] makes sure nobody reads it while it's being written. The curve is treated as
+/// piecewise linear between the samples, which makes the antiderivatives exact.
+pub struct SampledCurve {
+    values: Vec<AtomicF32>,
+    antiderivatives: Vec<AtomicF64>,
+    second_antiderivatives: Vec<AtomicF64>,
+}
+
+impl Default for SampledCurve {
+    fn default() -> Self {
+        Self {
+            values: (0..=TABLE_SEGMENTS).map(|_| AtomicF32::new(0.0)).collect(),
+            antiderivatives: (0..=TABLE_SEGMENTS).map(|_| AtomicF64::new(0.0)).collect(),
+            second_antiderivatives: (0..=TABLE_SEGMENTS).map(|_| AtomicF64::new(0.0)).collect(),
+        }
+    }
+}
+
+impl SampledCurve {
+    fn update(&self, curve: &CustomCurve) {
+        let control_points = curve.control_points();
+        let spline = Spline::new(&control_points);
+
+        let mut previous: Option<(f64, f64, f64)> = None;
+        for (i, ((value, antiderivative), second_antiderivative)) in self
+            .values
+            .iter()
+            .zip(&self.antiderivatives)
+            .zip(&self.second_antiderivatives)
+            .enumerate()
+        {
+            let x = -1.0 + i as f64 * SEGMENT_WIDTH;
+            let v = spline.evaluate(x as f32) as f64;
+            let (f, g) = match previous {
+                None => (0.0, 0.0),
+                Some((v0, f0, g0)) => (
+                    f0 + SEGMENT_WIDTH * (v0 + v) / 2.0,
+                    g0 + SEGMENT_WIDTH * f0 + SEGMENT_WIDTH.powi(2) * (2.0 * v0 + v) / 6.0,
+                ),
+            };
+            value.store(v as f32, Ordering::Relaxed);
+            antiderivative.store(f, Ordering::Relaxed);
+            second_antiderivative.store(g, Ordering::Relaxed);
+            previous = Some((v, f, g));
+        }
+    }
+
+    pub fn calculate(&self, x: f32) -> f32 {
+        let (i, offset, slope) = self.segment(x as f64);
+        (self.value(i) + slope * offset) as f32
+    }
+
+    pub fn antiderivative(&self, x: f64) -> f64 {
+        let (i, offset, slope) = self.segment(x);
+        self.antiderivatives[i].load(Ordering::Relaxed)
+            + self.value(i) * offset
+            + slope * offset.powi(2) / 2.0
+    }
+
+    pub fn second_antiderivative(&self, x: f64) -> f64 {
+        let (i, offset, slope) = self.segment(x);
+        self.second_antiderivatives[i].load(Ordering::Relaxed)
+            + self.antiderivatives[i].load(Ordering::Relaxed) * offset
+            + self.value(i) * offset.powi(2) / 2.0
+            + slope * offset.powi(3) / 6.0
+    }
+
+    fn value(&self, i: usize) -> f64 {
+        self.values[i].load(Ordering::Relaxed) as f64
+    }
+
+    /// The table entry to start from, the distance from it, and the slope after it. Outside of the
+    /// table the curve is flat.
+    fn segment(&self, x: f64) -> (usize, f64, f64) {
+        if x <= -1.0 {
+            return (0, x + 1.0, 0.0);
+        }
+        if x >= 1.0 {
+            return (TABLE_SEGMENTS, x - 1.0, 0.0);
+        }
+        let i = (((x + 1.0) / SEGMENT_WIDTH) as usize).min(TABLE_SEGMENTS - 1);
+        let offset = x - (-1.0 + i as f64 * SEGMENT_WIDTH);
+        let slope = (self.value(i + 1) - self.value(i)) / SEGMENT_WIDTH;
+        (i, offset, slope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn asymmetric_curve() -> CustomCurve {
+        CustomCurve {
+            points: vec![(-1.0, -0.5), (-0.2, -0.4), (0.3, 0.7), (1.0, 0.8)],
+            symmetric: false,
+        }
+    }
+
+    #[test]
+    fn table_matches_spline() {
+        for curve in [CustomCurve::default(), asymmetric_curve()] {
+            let table = CustomTable::default();
+            table.update(&curve);
+            let control_points = curve.control_points();
+            let spline = Spline::new(&control_points);
+            let table = table.read();
+            for i in 0..=1000 {
+                let x = -1.2 + i as f32 * 2.4 / 1000.0;
+                assert!((table.calculate(x) - spline.evaluate(x)).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn antiderivatives_match_integrals() {
+        let table = CustomTable::default();
+        table.update(&asymmetric_curve());
+        let table = table.read();
+        let steps = 20_000;
+        let step = 2.4 / steps as f64;
+        let (mut integral, mut second_integral) = (0.0, 0.0);
+        let mut x = -1.2;
+        for _ in 0..steps {
+            // The midpoint rule, which is close enough with this many steps
+            let value = table.calculate((x + step / 2.0) as f32) as f64;
+            second_integral += step * integral + step * step / 2.0 * value;
+            integral += step * value;
+            x += step;
+            let start = -1.2;
+            assert!(
+                (table.antiderivative(x) - table.antiderivative(start) - integral).abs() < 1e-4
+            );
+            let second_antiderivative = table.second_antiderivative(x)
+                - table.second_antiderivative(start)
+                - table.antiderivative(start) * (x - start);
+            assert!((second_antiderivative - second_integral).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn readers_keep_their_table_until_dropped() {
+        let table = CustomTable::default();
+        let old = table.read();
+        let before = old.calculate(0.5);
+        table.update(&asymmetric_curve());
+        assert_eq!(old.calculate(0.5), before);
+        assert_ne!(table.read().calculate(0.5), before);
+        drop(old);
+        // Both tables are free again, so more updates don't wait
+        table.update(&CustomCurve::default());
+        table.update(&asymmetric_curve());
+    }
+
+    #[test]
+    fn saved_curves_are_validated() {
+        let empty = CustomCurve::from(UncheckedCurve {
+            points: Vec::new(),
+            symmetric: false,
+        });
+        assert_eq!(empty.points(), CustomCurve::default().points());
+
+        let messy = CustomCurve::from(UncheckedCurve {
+            points: vec![
+                (0.5, 2.0),
+                (f32::NAN, 0.0),
+                (-0.5, -0.1),
+                (0.5, 0.3),
+                (0.9, 0.9),
+            ],
+            symmetric: false,
+        });
+        assert_eq!(messy.points(), &[(-1.0, -0.1), (0.5, 1.0), (1.0, 0.9)]);
+        assert!(messy.is_end_point(2));
+
+        let symmetric = CustomCurve::from(UncheckedCurve {
+            points: vec![(-0.5, 0.5), (0.0, 0.0)],
+            symmetric: true,
+        });
+        assert_eq!(symmetric.points(), CustomCurve::default().points());
+    }
+}
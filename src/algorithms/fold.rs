@@ -0,0 +1,155 @@
+use std::f64::consts::{FRAC_2_PI, FRAC_PI_2};
+
+/// Where the segments of the Buchla-style folder start.
+const BUCHLA_THRESHOLDS: [f64; 5] = [0.5, 1.3, 2.1, 2.9, 3.7];
+/// The slope of every segment of the Buchla-style folder, starting at zero. The uneven slopes make
+/// every fold a little different, like the folding cells of a Buchla 259. Past the last threshold
+/// the output stays put.
+const BUCHLA_SLOPES: [f64; 6] = [2.0, -2.1, 1.8, -1.7, 1.2, 0.0];
+
+/// How the wavefolders fold, shared by all of them.
+#[derive(Clone, Copy)]
+pub struct FoldSettings {
+    /// The gain going into the folder, which sets how many times a full-scale signal folds.
+    pub folds: f32,
+    /// Gives the positive half more gain than the negative half or the other way around, from -1
+    /// to 1.
+    pub symmetry: f32,
+}
+
+impl Default for FoldSettings {
+    fn default() -> Self {
+        Self {
+            folds: 1.0,
+            symmetry: 0.0,
+        }
+    }
+}
+
+impl FoldSettings {
+    /// The gain for the half of the signal `x` is in.
+    fn gain(self, x: f64) -> f64 {
+        let skew = if x >= 0.0 {
+            self.symmetry
+        } else {
+            -self.symmetry
+        };
+        self.folds as f64 * 2f64.powf(skew as f64)
+    }
+}
+
+/// A wavefolder's shape along with its antiderivatives. All of them are zero at zero, which is what
+/// lets [`FoldSettings::symmetry`] scale the two halves separately without breaking them.
+pub struct Folder {
+    pub function: fn(f64) -> f64,
+    pub antiderivative: fn(f64) -> f64,
+    pub second_antiderivative: fn(f64) -> f64,
+}
+
+pub const TRIANGLE: Folder = Folder {
+    function: triangle,
+    antiderivative: triangle_antiderivative,
+    second_antiderivative: triangle_second_antiderivative,
+};
+
+pub const SINE: Folder = Folder {
+    function: sine,
+    antiderivative: sine_antiderivative,
+    second_antiderivative: sine_second_antiderivative,
+};
+
+pub const BUCHLA: Folder = Folder {
+    function: buchla,
+    antiderivative: buchla_antiderivative,
+    second_antiderivative: buchla_second_antiderivative,
+};
+
+impl Folder {
+    pub fn calculate(&self, x: f32, settings: FoldSettings) -> f32 {
+        let x = x as f64;
+        (self.function)(settings.gain(x) * x) as f32
+    }
+
+    pub fn antiderivative(&self, x: f64, settings: FoldSettings) -> f64 {
+        let gain = settings.gain(x);
+        (self.antiderivative)(gain * x) / gain
+    }
+
+    pub fn second_antiderivative(&self, x: f64, settings: FoldSettings) -> f64 {
+        let gain = settings.gain(x);
+        (self.second_antiderivative)(gain * x) / (gain * gain)
+    }
+}
+
+/// Splits `x` into the number of whole periods of the triangle wave and the remainder in `[-1, 3)`.
+fn triangle_period(x: f64) -> (f64, f64) {
+    let periods = ((x + 1.0) / 4.0).floor();
+    (periods, x - 4.0 * periods)
+}
+
+/// Follows the input up to ±1 and then reflects back, forever.
+fn triangle(x: f64) -> f64 {
+    let (_, r) = triangle_period(x);
+    if r <= 1.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+fn triangle_antiderivative(x: f64) -> f64 {
+    // Every period integrates to zero
+    let (_, r) = triangle_period(x);
+    if r <= 1.0 {
+        0.5 * r * r
+    } else {
+        -0.5 * r * r + 2.0 * r - 1.0
+    }
+}
+
+fn triangle_second_antiderivative(x: f64) -> f64 {
+    // The first antiderivative integrates to 2 over every period
+    let (periods, r) = triangle_period(x);
+    let remainder = if r <= 1.0 {
+        r.powi(3) / 6.0
+    } else {
+        -r.powi(3) / 6.0 + r * r - r + 1.0 / 3.0
+    };
+    2.0 * periods + remainder
+}
+
+fn sine(x: f64) -> f64 {
+    (FRAC_PI_2 * x).sin()
+}
+
+fn sine_antiderivative(x: f64) -> f64 {
+    FRAC_2_PI * (1.0 - (FRAC_PI_2 * x).cos())
+}
+
+fn sine_second_antiderivative(x: f64) -> f64 {
+    FRAC_2_PI * x - FRAC_2_PI.powi(2) * (FRAC_PI_2 * x).sin()
+}
+
+/// Piecewise linear, so both antiderivatives are sums of ramps raised to a power.
+fn buchla_ramps(x: f64, power: i32) -> f64 {
+    let y = x.abs();
+    let mut sum = BUCHLA_SLOPES[0] * y.powi(power);
+    for (i, threshold) in BUCHLA_THRESHOLDS.iter().enumerate() {
+        if y > *threshold {
+            sum += (BUCHLA_SLOPES[i + 1] - BUCHLA_SLOPES[i]) * (y - threshold).powi(power);
+        }
+    }
+    sum
+}
+
+fn buchla(x: f64) -> f64 {
+    buchla_ramps(x, 1) * x.signum()
+}
+
+fn buchla_antiderivative(x: f64) -> f64 {
+    buchla_ramps(x, 2) / 2.0
+}
+
+fn buchla_second_antiderivative(x: f64) -> f64 {
+    buchla_ramps(x, 3) / 6.0 * x.signum()
+}
@@ -0,0 +1,295 @@
+use crate::algorithms::{AlgorithmContext, Blend, DistortionAlgorithm};
+use crate::dsp::adaa::AntiAliasing;
+use crate::dsp::chain::{Chain, ChainSettings};
+use crate::dsp::crossover::{Crossover, CrossoverCoefficients, MAX_BANDS};
+use crate::dsp::crusher::{Crusher, CrusherPosition, CrusherSettings};
+use crate::dsp::delay::DelayLine;
+use crate::dsp::feedback::{Feedback, FeedbackSettings};
+use crate::dsp::filter::DcBlocker;
+use crate::dsp::noise::{NoiseGenerator, NoiseMode, NoiseRng, NoiseSettings};
+use crate::dsp::oversampling::Oversampling;
+use crate::dsp::parallel::{ParallelPath, ParallelSettings};
+use crate::dsp::shaper::Shaper;
+use crate::dsp::tone::{ToneCoefficients, ToneFilter};
+
+/// The values one channel needs for processing a single sample.
+#[derive(Clone, Copy)]
+pub struct ChannelSettings<'a> {
+    pub drive: f32,
+    pub algorithm: Blend,
+    /// The algorithm every path morphs towards, including the bands.
+    pub morph_algorithm: Blend,
+    pub morph: f32,
+    /// Added to the driven signal before shaping.
+    pub bias: f32,
+    /// Applied by the caller, since the dry signal doesn't pass through the channel.
+    pub mix: f32,
+    pub noise: NoiseSettings,
+    pub crusher: CrusherSettings,
+    pub feedback: FeedbackSettings<'a>,
+    pub chain: &'a ChainSettings,
+    pub parallel: &'a ParallelSettings,
+    pub oversampling: Oversampling,
+    pub anti_aliasing: AntiAliasing,
+    pub dc_block: bool,
+    /// See [`DcBlocker::coefficient()`].
+    pub dc_block_coefficient: f32,
+    pub context: AlgorithmContext<'a>,
+    pub pre_filter: &'a ToneCoefficients,
+    pub post_filter: &'a ToneCoefficients,
+}
+
+impl ChannelSettings<'_> {
+    fn blend(&self, algorithm: Blend) -> Blend {
+        Blend::morph(algorithm, self.morph_algorithm, self.morph)
+    }
+}
+
+/// The band settings shared by both channels. With a single band the channel's own settings are
+/// used instead.
+#[derive(Default)]
+pub struct MultibandSettings {
+    pub bands: usize,
+    pub band_settings: [BandSettings; MAX_BANDS],
+    pub crossover: CrossoverCoefficients,
+    /// The latency of a band's shaper, which the band's dry signal is delayed by in samples.
+    pub dry_delay: f32,
+}
+
+#[derive(Clone, Copy)]
+pub struct BandSettings {
+    pub algorithm: Blend,
+    /// Multiplied with the channel's drive.
+    pub drive: f32,
+    pub mix: f32,
+    pub gain: f32,
+    /// False when the band is muted, or when another band is soloed.
+    pub audible: bool,
+    pub bypass: bool,
+}
+
+impl Default for BandSettings {
+    fn default() -> Self {
+        Self {
+            algorithm: DistortionAlgorithm::SoftClip.into(),
+            drive: 1.0,
+            mix: 1.0,
+            gain: 1.0,
+            audible: true,
+            bypass: false,
+        }
+    }
+}
+
+impl BandSettings {
+    fn output(&self, dry: f32, wet: f32) -> f32 {
+        if !self.audible {
+            0.0
+        } else if self.bypass {
+            dry
+        } else {
+            mix(dry, wet, self.mix) * self.gain
+        }
+    }
+}
+
+/// Everything that has to be remembered between samples for a single channel. In mid/side mode the
+/// channels hold the mid and side signals instead of left and right.
+pub struct ChannelState {
+    rng: NoiseRng,
+    noise: NoiseGenerator,
+    crusher: Crusher,
+    feedback: Feedback,
+    crossover: Crossover,
+    shapers: [Shaper; MAX_BANDS],
+    band_delays: [DelayLine; MAX_BANDS],
+    chain: Chain,
+    parallel: ParallelPath,
+    /// Whether the parallel path was processed for the last sample.
+    parallel_running: bool,
+    dc_blocker: DcBlocker,
+    parallel_dc_blocker: DcBlocker,
+    pre_filter: ToneFilter,
+    post_filter: ToneFilter,
+}
+
+impl ChannelState {
+    pub fn new(channel: usize) -> Self {
+        Self {
+            rng: NoiseRng::new(channel),
+            noise: NoiseGenerator::default(),
+            crusher: Crusher::default(),
+            feedback: Feedback::default(),
+            crossover: Crossover::default(),
+            shapers: Default::default(),
+            band_delays: Default::default(),
+            chain: Chain::default(),
+            parallel: ParallelPath::default(),
+            parallel_running: false,
+            dc_blocker: DcBlocker::default(),
+            parallel_dc_blocker: DcBlocker::default(),
+            pre_filter: ToneFilter::default(),
+            post_filter: ToneFilter::default(),
+        }
+    }
+
+    /// Clears the filter states. Needed whenever the oversampling or anti-aliasing changes.
+    pub fn reset(&mut self) {
+        self.noise.reset();
+        self.crusher.reset();
+        self.feedback.reset();
+        self.crossover.reset();
+        for shaper in &mut self.shapers {
+            shaper.reset();
+        }
+        for delay in &mut self.band_delays {
+            delay.reset();
+        }
+        self.chain.reset();
+        self.parallel.reset();
+        self.dc_blocker = DcBlocker::default();
+        self.parallel_dc_blocker = DcBlocker::default();
+        self.pre_filter.reset();
+        self.post_filter.reset();
+    }
+
+    /// Restarts the noise from its seed, along with the noise filters and the bitcrusher which uses
+    /// the same random numbers. Unlike [`reset()`][Self::reset()] this changes the sound, so it's
+    /// only done when the user asks for it.
+    pub fn reset_noise(&mut self) {
+        self.rng.reset();
+        self.noise.reset();
+        self.crusher.reset();
+    }
+
+    /// The next noise sample for this channel. The channels are always left and right here, so the
+    /// caller has to encode the noise along with the signal.
+    pub fn next_noise(&mut self, settings: &NoiseSettings) -> f32 {
+        self.noise.next(settings, &mut self.rng)
+    }
+
+    /// Returns the main path's and the parallel path's wet signals. `noise` is this channel's part
+    /// of [`next_noise()`][Self::next_noise()] after encoding.
+    pub fn process(
+        &mut self,
+        sample: f32,
+        noise: f32,
+        settings: &ChannelSettings,
+        multiband: &MultibandSettings,
+    ) -> (f32, f32) {
+        let parallel = if settings.parallel.is_audible() {
+            let shaped = self.parallel.process(
+                sample,
+                settings.parallel,
+                settings.context,
+                settings.oversampling,
+                settings.anti_aliasing,
+            );
+            block_dc(&mut self.parallel_dc_blocker, settings, shaped)
+        } else {
+            // Turning the path back up then starts from silence instead of from where it stopped
+            if self.parallel_running {
+                self.parallel.reset();
+                self.parallel_dc_blocker = DcBlocker::default();
+            }
+            0.0
+        };
+        self.parallel_running = settings.parallel.is_audible();
+
+        let shaped = self.shape(sample, noise, settings, multiband);
+        let shaped = self.chain.process(
+            shaped,
+            settings.chain,
+            settings.context,
+            settings.oversampling,
+            settings.anti_aliasing,
+        );
+        self.feedback.write(&settings.feedback, shaped);
+        let shaped = match settings.noise.mode {
+            NoiseMode::Additive => shaped + noise,
+            NoiseMode::Multiplicative => shaped * (1.0 + noise),
+            NoiseMode::PreShaper => shaped,
+        };
+        let shaped = if settings.crusher.is_at(CrusherPosition::Post) {
+            self.crusher
+                .process(&settings.crusher, shaped, &mut self.rng)
+        } else {
+            shaped
+        };
+        let output = block_dc(&mut self.dc_blocker, settings, shaped);
+        let main = self.post_filter.process(settings.post_filter, output);
+        (main, parallel)
+    }
+
+    fn shape(
+        &mut self,
+        sample: f32,
+        noise: f32,
+        settings: &ChannelSettings,
+        multiband: &MultibandSettings,
+    ) -> f32 {
+        let input = self.pre_filter.process(settings.pre_filter, sample);
+        let input = if settings.noise.mode == NoiseMode::PreShaper {
+            input + noise
+        } else {
+            input
+        };
+        let input = if settings.crusher.is_at(CrusherPosition::Pre) {
+            self.crusher
+                .process(&settings.crusher, input, &mut self.rng)
+        } else {
+            input
+        };
+        // The feedback goes through the drive along with the input
+        let input = input + self.feedback.read(&settings.feedback);
+        if multiband.bands <= 1 {
+            return self.shapers[0].process(
+                input * settings.drive,
+                &settings.blend(settings.algorithm),
+                settings.context,
+                settings.bias,
+                settings.oversampling,
+                settings.anti_aliasing,
+            );
+        }
+
+        let bands = self
+            .crossover
+            .split(&multiband.crossover, multiband.bands, input);
+        bands
+            .into_iter()
+            .zip(&mut self.shapers)
+            .zip(&mut self.band_delays)
+            .zip(&multiband.band_settings)
+            .take(multiband.bands)
+            .map(|(((band, shaper), delay), band_settings)| {
+                // Muted bands are still processed so they come back in without a click
+                let wet = shaper.process(
+                    band * settings.drive * band_settings.drive,
+                    &settings.blend(band_settings.algorithm),
+                    settings.context,
+                    settings.bias,
+                    settings.oversampling,
+                    settings.anti_aliasing,
+                );
+                let dry = delay.process(band, multiband.dry_delay);
+                band_settings.output(dry, wet)
+            })
+            .sum()
+    }
+}
+
+fn block_dc(dc_blocker: &mut DcBlocker, settings: &ChannelSettings, input: f32) -> f32 {
+    // The blocker keeps running while it's switched off so turning it back on doesn't click
+    let blocked = dc_blocker.process(settings.dc_block_coefficient, input);
+    if settings.dc_block {
+        blocked
+    } else {
+        input
+    }
+}
+
+#[inline]
+pub fn mix(dry: f32, wet: f32, mix: f32) -> f32 {
+    wet * mix + dry * (1.0 - mix)
+}
@@ -1,20 +1,45 @@
 use atomic_float::AtomicF32;
 use nih_plug::prelude::*;
 use paste::paste;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
 use crate::algorithms::DistortionAlgorithm;
 
 pub struct UIData {
+    pub sample_rate: AtomicF32,
     pub algorithm: AtomicUsize,
     pub drive: AtomicF32,
+    pub mix: AtomicF32,
+    pub noise: AtomicF32,
+    pub morph_algorithm: AtomicUsize,
+    pub morph: AtomicF32,
+    pub bias: AtomicF32,
+    /// Whether the right or side channel has its own settings.
+    pub split: AtomicBool,
+    pub algorithm_b: AtomicUsize,
+    pub drive_b: AtomicF32,
+    /// The applied auto gain compensation in decibels.
+    pub auto_gain: AtomicF32,
+    /// The limiter's gain reduction in decibels.
+    pub limiter_gain: AtomicF32,
 }
 
 impl Default for UIData {
     fn default() -> Self {
         Self {
+            sample_rate: AtomicF32::new(44100.0),
             algorithm: AtomicUsize::new(DistortionAlgorithm::SoftClip.into()),
             drive: AtomicF32::new(util::db_to_gain(0.0)),
+            mix: AtomicF32::new(1.0),
+            noise: AtomicF32::new(0.0),
+            morph_algorithm: AtomicUsize::new(DistortionAlgorithm::SoftClip.into()),
+            morph: AtomicF32::new(0.0),
+            bias: AtomicF32::new(0.0),
+            split: AtomicBool::new(false),
+            algorithm_b: AtomicUsize::new(DistortionAlgorithm::SoftClip.into()),
+            drive_b: AtomicF32::new(util::db_to_gain(0.0)),
+            auto_gain: AtomicF32::new(0.0),
+            limiter_gain: AtomicF32::new(0.0),
         }
     }
 }
@@ -40,9 +65,31 @@ macro_rules! set {
 
 #[allow(dead_code)]
 impl UIData {
+    get!(sample_rate f32);
     get!(algorithm DistortionAlgorithm);
     get!(drive f32);
+    get!(mix f32);
+    get!(noise f32);
+    get!(morph_algorithm DistortionAlgorithm);
+    get!(morph f32);
+    get!(bias f32);
+    get!(split bool);
+    get!(algorithm_b DistortionAlgorithm);
+    get!(drive_b f32);
+    get!(auto_gain f32);
+    get!(limiter_gain f32);
 
+    set!(sample_rate f32);
     set!(algorithm DistortionAlgorithm);
     set!(drive f32);
+    set!(mix f32);
+    set!(noise f32);
+    set!(morph_algorithm DistortionAlgorithm);
+    set!(morph f32);
+    set!(bias f32);
+    set!(split bool);
+    set!(algorithm_b DistortionAlgorithm);
+    set!(drive_b f32);
+    set!(auto_gain f32);
+    set!(limiter_gain f32);
 }
@@ -0,0 +1,19 @@
+pub mod adaa;
+pub mod analog;
+pub mod auto_gain;
+pub mod chain;
+pub mod crossfade;
+pub mod crossover;
+pub mod crusher;
+pub mod delay;
+pub mod envelope;
+pub mod feedback;
+pub mod filter;
+pub mod limiter;
+pub mod listen;
+pub mod noise;
+pub mod oversampling;
+pub mod parallel;
+pub mod shaper;
+pub mod stereo;
+pub mod tone;
@@ -0,0 +1,225 @@
+use nih_plug::prelude::Enum;
+
+use crate::algorithms::{AlgorithmContext, Blend};
+
+/// Below this difference between inputs the divided differences are too ill-conditioned to use,
+/// so the blend is evaluated directly at the midpoint instead.
+const TOLERANCE: f64 = 1e-5;
+
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum AntiAliasing {
+    Off,
+    #[name = "ADAA 1st Order"]
+    FirstOrder,
+    #[name = "ADAA 2nd Order"]
+    SecondOrder,
+}
+
+impl AntiAliasing {
+    /// The group delay of the anti-aliasing in samples at the rate it's running at.
+    pub fn latency(self) -> f32 {
+        match self {
+            AntiAliasing::Off => 0.0,
+            AntiAliasing::FirstOrder => 0.5,
+            AntiAliasing::SecondOrder => 1.0,
+        }
+    }
+}
+
+/// Antiderivative anti-aliasing for a single channel. This only needs to remember the previous
+/// inputs, so the blend can change freely between samples.
+#[derive(Default)]
+pub struct Adaa {
+    x1: f64,
+    x2: f64,
+}
+
+impl Adaa {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn process(
+        &mut self,
+        anti_aliasing: AntiAliasing,
+        blend: &Blend,
+        context: AlgorithmContext,
+        x: f32,
+    ) -> f32 {
+        let x0 = x as f64;
+        let y = match anti_aliasing {
+            AntiAliasing::Off => blend.calculate(x, context) as f64,
+            AntiAliasing::FirstOrder => first_order(blend, context, x0, self.x1),
+            AntiAliasing::SecondOrder => second_order(blend, context, x0, self.x1, self.x2),
+        };
+        self.x2 = self.x1;
+        self.x1 = x0;
+        y as f32
+    }
+}
+
+/// Delays a signal by as much as the anti-aliasing delays the shaped signal, for the parts of a blend
+/// that can't be anti-aliased.
+#[derive(Default)]
+pub struct AdaaDelay {
+    previous: f32,
+}
+
+impl AdaaDelay {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn process(&mut self, anti_aliasing: AntiAliasing, x: f32) -> f32 {
+        let y = match anti_aliasing {
+            AntiAliasing::Off => x,
+            // Half a sample, which first-order anti-aliasing also does to a straight line
+            AntiAliasing::FirstOrder => 0.5 * (x + self.previous),
+            AntiAliasing::SecondOrder => self.previous,
+        };
+        self.previous = x;
+        y
+    }
+}
+
+fn first_order(blend: &Blend, context: AlgorithmContext, x0: f64, x1: f64) -> f64 {
+    let diff = x0 - x1;
+    if diff.abs() < TOLERANCE {
+        evaluate(blend, context, 0.5 * (x0 + x1))
+    } else {
+        (blend.antiderivative(x0, context) - blend.antiderivative(x1, context)) / diff
+    }
+}
+
+fn second_order(blend: &Blend, context: AlgorithmContext, x0: f64, x1: f64, x2: f64) -> f64 {
+    let diff = x0 - x2;
+    if diff.abs() < TOLERANCE {
+        let x_bar = 0.5 * (x0 + x2);
+        let delta = x_bar - x1;
+        if delta.abs() < TOLERANCE {
+            evaluate(blend, context, 0.5 * (x_bar + x1))
+        } else {
+            2.0 / delta
+                * (blend.antiderivative(x_bar, context)
+                    + (blend.second_antiderivative(x1, context)
+                        - blend.second_antiderivative(x_bar, context))
+                        / delta)
+        }
+    } else {
+        2.0 / diff
+            * (divided_difference(blend, context, x0, x1)
+                - divided_difference(blend, context, x1, x2))
+    }
+}
+
+/// The first divided difference of the second antiderivative.
+fn divided_difference(blend: &Blend, context: AlgorithmContext, a: f64, b: f64) -> f64 {
+    let diff = a - b;
+    if diff.abs() < TOLERANCE {
+        blend.antiderivative(0.5 * (a + b), context)
+    } else {
+        (blend.second_antiderivative(a, context) - blend.second_antiderivative(b, context)) / diff
+    }
+}
+
+fn evaluate(blend: &Blend, context: AlgorithmContext, x: f64) -> f64 {
+    blend.calculate(x as f32, context) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::custom::{CustomTable, SampledCurve};
+    use crate::algorithms::fold::FoldSettings;
+    use crate::algorithms::DistortionAlgorithm;
+    use crate::dsp::analog::AnalogCoefficients;
+
+    const ORDERS: [AntiAliasing; 3] = [
+        AntiAliasing::Off,
+        AntiAliasing::FirstOrder,
+        AntiAliasing::SecondOrder,
+    ];
+
+    /// The center of mass of an impulse response, which is the delay of a linear phase filter.
+    fn center(response: &[f32]) -> f32 {
+        let sum: f32 = response.iter().sum();
+        response
+            .iter()
+            .enumerate()
+            .map(|(i, sample)| i as f32 * sample)
+            .sum::<f32>()
+            / sum
+    }
+
+    /// A slowly changing input barely aliases, so anti-aliasing should give the same output as
+    /// evaluating the algorithm directly at the delayed input.
+    #[test]
+    fn matches_direct_evaluation() {
+        let table = CustomTable::default();
+        let custom = table.read();
+        let analog = AnalogCoefficients::default();
+        let context = AlgorithmContext {
+            custom: &custom,
+            fold: FoldSettings::default(),
+            analog: &analog,
+        };
+        // The steps and folds are only off for the few samples around their corners
+        let input: Vec<f32> = (0..2000)
+            .map(|i| 3.0 * (i as f32 * std::f32::consts::TAU / 2000.0).sin())
+            .collect();
+        for index in 0..DistortionAlgorithm::variants().len() {
+            let algorithm = DistortionAlgorithm::from_index(index);
+            if algorithm.is_stateful() {
+                continue;
+            }
+            let blend = algorithm.into();
+            for anti_aliasing in [AntiAliasing::FirstOrder, AntiAliasing::SecondOrder] {
+                let mut adaa = Adaa::default();
+                let mut delay = AdaaDelay::default();
+                let error = input
+                    .iter()
+                    .map(|&x| {
+                        let anti_aliased = adaa.process(anti_aliasing, &blend, context, x);
+                        let direct = algorithm.calculate(delay.process(anti_aliasing, x), context);
+                        (anti_aliased - direct).abs()
+                    })
+                    .sum::<f32>()
+                    / input.len() as f32;
+                assert!(
+                    error < 1e-3,
+                    "algorithm {} is off by {} on average",
+                    index,
+                    error
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn delay_matches_anti_aliasing() {
+        let custom = SampledCurve::default();
+        let analog = AnalogCoefficients::default();
+        let context = AlgorithmContext {
+            custom: &custom,
+            fold: FoldSettings::default(),
+            analog: &analog,
+        };
+        // Hard clipping is a straight line below one
+        let blend = DistortionAlgorithm::HardClip.into();
+        for anti_aliasing in ORDERS {
+            let mut adaa = Adaa::default();
+            let mut delay = AdaaDelay::default();
+            let impulse = (0..8).map(|i| if i == 0 { 0.5 } else { 0.0 });
+            let (anti_aliased, delayed): (Vec<f32>, Vec<f32>) = impulse
+                .map(|x| {
+                    (
+                        adaa.process(anti_aliasing, &blend, context, x),
+                        delay.process(anti_aliasing, x),
+                    )
+                })
+                .unzip();
+            assert!((center(&anti_aliased) - center(&delayed)).abs() < 1e-6);
+            assert!((center(&delayed) - anti_aliasing.latency()).abs() < 1e-6);
+        }
+    }
+}
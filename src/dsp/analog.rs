@@ -0,0 +1,165 @@
+use super::envelope::EnvelopeFollower;
+use super::filter::{Biquad, BiquadCoefficients};
+use crate::algorithms;
+
+/// How quickly grid current charges the coupling capacitor.
+const GRID_CHARGE_MS: f32 = 1.0;
+/// The furthest grid current can shift the tube's bias.
+const MAX_BIAS_SHIFT: f32 = 2.0;
+/// Where the tape's emphasis filters shelve.
+const TAPE_EMPHASIS_FREQUENCY: f32 = 3_000.0;
+/// The shape of the anhysteretic magnetization. A third gives the tape unity gain for quiet
+/// signals.
+const TAPE_SHAPE: f64 = 1.0 / 3.0;
+/// How strongly the magnetic domains couple to each other.
+const TAPE_COUPLING: f64 = 2e-3;
+/// How much of the magnetization is reversible.
+const TAPE_REVERSIBILITY: f64 = 0.1;
+/// The range the hysteresis parameter maps to, in the same units as the input.
+const MIN_TAPE_COERCIVITY: f64 = 0.01;
+const MAX_TAPE_COERCIVITY: f64 = 0.4;
+
+/// The settings for the stateful algorithms, shared between the channels. The filters and time
+/// constants run at the oversampled rate, and are only recomputed when the settings change.
+pub struct AnalogCoefficients {
+    /// How much of the positive grid voltage charges the coupling capacitor every sample.
+    pub grid_charge: f32,
+    /// See [`EnvelopeFollower::coefficient()`].
+    pub grid_recovery: f32,
+    /// The width of the hysteresis loop.
+    pub tape_coercivity: f64,
+    /// Boosts the highs before the hysteresis so they saturate sooner, and
+    /// [`tape_de_emphasis`][Self::tape_de_emphasis] cuts them back down afterwards.
+    pub tape_emphasis: BiquadCoefficients,
+    pub tape_de_emphasis: BiquadCoefficients,
+    /// The settings the coefficients were last computed for.
+    settings: Option<(f32, f32, f32, f32, f32)>,
+}
+
+impl Default for AnalogCoefficients {
+    fn default() -> Self {
+        Self {
+            grid_charge: 0.0,
+            grid_recovery: 0.0,
+            tape_coercivity: MIN_TAPE_COERCIVITY,
+            tape_emphasis: BiquadCoefficients::default(),
+            tape_de_emphasis: BiquadCoefficients::default(),
+            settings: None,
+        }
+    }
+}
+
+impl AnalogCoefficients {
+    /// `grid_current` and `hysteresis` go from zero to one, and `emphasis` is in decibels.
+    pub fn update(
+        &mut self,
+        sample_rate: f32,
+        grid_current: f32,
+        recovery_ms: f32,
+        hysteresis: f32,
+        emphasis: f32,
+    ) {
+        let settings = Some((sample_rate, grid_current, recovery_ms, hysteresis, emphasis));
+        if settings == self.settings {
+            return;
+        }
+        self.settings = settings;
+
+        self.grid_charge =
+            grid_current * (1.0 - EnvelopeFollower::coefficient(sample_rate, GRID_CHARGE_MS));
+        self.grid_recovery = EnvelopeFollower::coefficient(sample_rate, recovery_ms);
+        self.tape_coercivity =
+            MIN_TAPE_COERCIVITY + (MAX_TAPE_COERCIVITY - MIN_TAPE_COERCIVITY) * hysteresis as f64;
+        self.tape_emphasis =
+            BiquadCoefficients::high_shelf(sample_rate, TAPE_EMPHASIS_FREQUENCY, emphasis);
+        self.tape_de_emphasis =
+            BiquadCoefficients::high_shelf(sample_rate, TAPE_EMPHASIS_FREQUENCY, -emphasis);
+    }
+}
+
+/// A triode stage. When the grid is driven positive, grid current charges the coupling capacitor
+/// and pushes the bias down, which makes the tube distort less evenly until it recovers.
+#[derive(Default)]
+pub struct Tube {
+    bias_shift: f32,
+}
+
+impl Tube {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn process(&mut self, input: f32, coefficients: &AnalogCoefficients) -> f32 {
+        let grid = input + self.bias_shift;
+        if grid > 0.0 {
+            self.bias_shift -= coefficients.grid_charge * grid;
+        }
+        self.bias_shift = (self.bias_shift * coefficients.grid_recovery).max(-MAX_BIAS_SHIFT);
+        algorithms::tube(grid)
+    }
+}
+
+/// Magnetic tape, using the Jiles-Atherton model of hysteresis solved with a second order
+/// Runge-Kutta step. The input is the magnetic field and the output the tape's magnetization, both
+/// normalized so the magnetization saturates at ±1.
+#[derive(Default)]
+pub struct Tape {
+    magnetization: f64,
+    field: f64,
+    emphasis: Biquad,
+    de_emphasis: Biquad,
+}
+
+impl Tape {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn process(&mut self, input: f32, coefficients: &AnalogCoefficients) -> f32 {
+        let field = self.emphasis.process(&coefficients.tape_emphasis, input) as f64;
+        let delta = field - self.field;
+        if delta != 0.0 {
+            let k = coefficients.tape_coercivity;
+            let m = self.magnetization;
+            let k1 = delta * magnetization_slope(m, self.field, delta, k);
+            let k2 = delta * magnetization_slope(m + 0.5 * k1, self.field + 0.5 * delta, delta, k);
+            self.magnetization = (m + k2).clamp(-1.0, 1.0);
+            if !self.magnetization.is_finite() {
+                self.magnetization = 0.0;
+            }
+        }
+        self.field = field;
+        self.de_emphasis
+            .process(&coefficients.tape_de_emphasis, self.magnetization as f32)
+    }
+}
+
+/// The Jiles-Atherton equation for how the magnetization changes with the field, `dM/dH`.
+fn magnetization_slope(magnetization: f64, field: f64, delta: f64, coercivity: f64) -> f64 {
+    let effective_field = (field + TAPE_COUPLING * magnetization) / TAPE_SHAPE;
+    let (anhysteretic, slope) = langevin(effective_field);
+    let difference = anhysteretic - magnetization;
+    let direction = delta.signum();
+    // The irreversible part only moves the magnetization towards the anhysteretic curve
+    let irreversible = if difference * direction > 0.0 {
+        (1.0 - TAPE_REVERSIBILITY) * difference
+            / ((1.0 - TAPE_REVERSIBILITY) * direction * coercivity - TAPE_COUPLING * difference)
+    } else {
+        0.0
+    };
+    let reversible = TAPE_REVERSIBILITY / TAPE_SHAPE * slope;
+    (irreversible + reversible) / (1.0 - TAPE_REVERSIBILITY * TAPE_COUPLING / TAPE_SHAPE * slope)
+}
+
+/// The Langevin function `coth(x) - 1/x` and its derivative.
+fn langevin(x: f64) -> (f64, f64) {
+    if x.abs() < 1e-4 {
+        (x / 3.0, 1.0 / 3.0)
+    } else {
+        let sinh = x.sinh();
+        (
+            1.0 / x.tanh() - 1.0 / x,
+            1.0 / (x * x) - 1.0 / (sinh * sinh),
+        )
+    }
+}
@@ -0,0 +1,63 @@
+use nih_plug::prelude::util;
+
+use super::envelope::EnvelopeFollower;
+
+/// How long the RMS measurements average over.
+const RMS_TIME_MS: f32 = 300.0;
+/// How slowly the compensation follows the measurements.
+const GAIN_SMOOTHING_MS: f32 = 1000.0;
+pub const MAX_COMPENSATION_DB: f32 = 24.0;
+/// Below this mean square level the input counts as silent, and the compensation is held.
+const SILENCE: f32 = 1e-6;
+
+/// Matches the RMS level of the processed signal to that of the input.
+pub struct AutoGain {
+    rms_coefficient: f32,
+    gain_coefficient: f32,
+    input_power: f32,
+    output_power: f32,
+    gain: f32,
+}
+
+impl Default for AutoGain {
+    fn default() -> Self {
+        Self {
+            rms_coefficient: 0.0,
+            gain_coefficient: 0.0,
+            input_power: 0.0,
+            output_power: 0.0,
+            gain: 1.0,
+        }
+    }
+}
+
+impl AutoGain {
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.rms_coefficient = EnvelopeFollower::coefficient(sample_rate, RMS_TIME_MS);
+        self.gain_coefficient = EnvelopeFollower::coefficient(sample_rate, GAIN_SMOOTHING_MS);
+    }
+
+    pub fn reset(&mut self) {
+        self.input_power = 0.0;
+        self.output_power = 0.0;
+        self.gain = 1.0;
+    }
+
+    /// Measures a stereo sample from before and after processing, and returns the gain that
+    /// compensates for the difference.
+    pub fn process(&mut self, input: [f32; 2], output: [f32; 2]) -> f32 {
+        let power = |[left, right]: [f32; 2]| (left * left + right * right) / 2.0;
+        self.input_power = power(input) + self.rms_coefficient * (self.input_power - power(input));
+        self.output_power =
+            power(output) + self.rms_coefficient * (self.output_power - power(output));
+
+        if self.input_power > SILENCE && self.output_power > SILENCE {
+            let max_gain = util::db_to_gain(MAX_COMPENSATION_DB);
+            let target = (self.input_power / self.output_power)
+                .sqrt()
+                .clamp(max_gain.recip(), max_gain);
+            self.gain = target + self.gain_coefficient * (self.gain - target);
+        }
+        self.gain
+    }
+}
@@ -0,0 +1,150 @@
+use nih_plug::prelude::Enum;
+
+use super::adaa::AntiAliasing;
+use super::oversampling::Oversampling;
+use super::shaper::Shaper;
+use super::tone::{ToneCoefficients, ToneFilter};
+use crate::algorithms::{AlgorithmContext, Blend, DistortionAlgorithm};
+
+/// The most stages in the chain, including the main algorithm as the first stage.
+pub const MAX_CHAIN_STAGES: usize = 4;
+/// The stages that come after the main algorithm.
+pub const MAX_EXTRA_STAGES: usize = MAX_CHAIN_STAGES - 1;
+
+/// The order the stages after the main algorithm run in. The names are the stage numbers, where
+/// the main algorithm is stage one. Stages that aren't in use are skipped, so with three stages
+/// `4, 3, 2` runs stage three before stage two.
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum StageOrder {
+    #[name = "2, 3, 4"]
+    TwoThreeFour,
+    #[name = "2, 4, 3"]
+    TwoFourThree,
+    #[name = "3, 2, 4"]
+    ThreeTwoFour,
+    #[name = "3, 4, 2"]
+    ThreeFourTwo,
+    #[name = "4, 2, 3"]
+    FourTwoThree,
+    #[name = "4, 3, 2"]
+    FourThreeTwo,
+}
+
+impl StageOrder {
+    /// The indices into the extra stages, in the order they run.
+    fn indices(self) -> [usize; MAX_EXTRA_STAGES] {
+        match self {
+            StageOrder::TwoThreeFour => [0, 1, 2],
+            StageOrder::TwoFourThree => [0, 2, 1],
+            StageOrder::ThreeTwoFour => [1, 0, 2],
+            StageOrder::ThreeFourTwo => [1, 2, 0],
+            StageOrder::FourTwoThree => [2, 0, 1],
+            StageOrder::FourThreeTwo => [2, 1, 0],
+        }
+    }
+
+    /// The indices of the extra stages that are in use with `stages` stages in total, in the
+    /// order they run.
+    pub fn active_indices(self, stages: usize) -> impl Iterator<Item = usize> {
+        let extra_stages = stages.saturating_sub(1);
+        self.indices()
+            .into_iter()
+            .filter(move |&index| index < extra_stages)
+    }
+}
+
+/// The stages after the main algorithm, shared by both channels. With a single stage the chain is
+/// disabled.
+pub struct ChainSettings {
+    pub stages: usize,
+    pub order: StageOrder,
+    pub stage_settings: [StageSettings; MAX_EXTRA_STAGES],
+}
+
+impl Default for ChainSettings {
+    fn default() -> Self {
+        Self {
+            stages: 0,
+            order: StageOrder::TwoThreeFour,
+            stage_settings: Default::default(),
+        }
+    }
+}
+
+pub struct StageSettings {
+    pub algorithm: Blend,
+    pub drive: f32,
+    /// Filters the signal coming from the previous stage.
+    pub filter: ToneCoefficients,
+}
+
+impl Default for StageSettings {
+    fn default() -> Self {
+        Self {
+            algorithm: DistortionAlgorithm::SoftClip.into(),
+            drive: 1.0,
+            filter: ToneCoefficients::default(),
+        }
+    }
+}
+
+/// The extra shaping stages for a single channel, like the gain stages of an amplifier.
+#[derive(Default)]
+pub struct Chain {
+    stages: [(ToneFilter, Shaper); MAX_EXTRA_STAGES],
+}
+
+impl Chain {
+    pub fn reset(&mut self) {
+        for (filter, shaper) in &mut self.stages {
+            filter.reset();
+            shaper.reset();
+        }
+    }
+
+    pub fn process(
+        &mut self,
+        input: f32,
+        chain: &ChainSettings,
+        context: AlgorithmContext,
+        oversampling: Oversampling,
+        anti_aliasing: AntiAliasing,
+    ) -> f32 {
+        // Every stage keeps its own state when the order changes
+        chain
+            .order
+            .active_indices(chain.stages)
+            .fold(input, |x, index| {
+                let (filter, shaper) = &mut self.stages[index];
+                let settings = &chain.stage_settings[index];
+                let filtered = filter.process(&settings.filter, x);
+                shaper.process(
+                    filtered * settings.drive,
+                    &settings.algorithm,
+                    context,
+                    0.0,
+                    oversampling,
+                    anti_aliasing,
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unused_stages_are_skipped() {
+        let active = |order: StageOrder, stages| order.active_indices(stages).collect::<Vec<_>>();
+        assert_eq!(active(StageOrder::FourThreeTwo, 1), []);
+        assert_eq!(active(StageOrder::FourThreeTwo, 3), [1, 0]);
+        assert_eq!(active(StageOrder::ThreeFourTwo, 4), [1, 2, 0]);
+        // Every order contains every stage exactly once
+        for index in 0..StageOrder::variants().len() {
+            let mut indices = active(StageOrder::from_index(index), MAX_CHAIN_STAGES);
+            indices.sort_unstable();
+            assert_eq!(indices, [0, 1, 2]);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::f32::consts::FRAC_PI_2;
+
+use crate::algorithms::{Blend, DistortionAlgorithm};
+
+/// The most algorithms a single fade blends at once. Changing the algorithm again mid-fade keeps
+/// everything that's still audible, so fast automation can stack up several of them.
+pub const MAX_FADE_TERMS: usize = 4;
+
+/// Algorithms that have faded out further than this are dropped when the fade starts over. A jump
+/// this small can't be heard.
+const INAUDIBLE_WEIGHT: f32 = 1e-3;
+
+/// Follows an algorithm parameter, crossfading from the old algorithm to the new one whenever it
+/// changes instead of switching instantly.
+#[derive(Clone, Copy)]
+pub struct AlgorithmFade {
+    /// Whatever was playing when the fade started, which may itself have been mid-fade.
+    outgoing: Blend,
+    current: DistortionAlgorithm,
+    /// Goes from zero to one over the course of the fade.
+    progress: f32,
+}
+
+impl AlgorithmFade {
+    pub fn new(algorithm: DistortionAlgorithm) -> Self {
+        Self {
+            outgoing: algorithm.into(),
+            current: algorithm,
+            progress: 1.0,
+        }
+    }
+
+    /// Advances the fade by one sample. `step` is how much of the fade a sample covers, so one
+    /// switches instantly.
+    pub fn next(&mut self, target: DistortionAlgorithm, step: f32) -> Blend {
+        if target != self.current {
+            // The new fade starts from exactly what's playing, so nothing gets cut off. Switching
+            // back to an algorithm that's still fading out just fades it back in from where it is,
+            // so it doesn't need a term of its own.
+            let playing = self.blend();
+            let others = playing
+                .without(target)
+                .without_quietest(INAUDIBLE_WEIGHT, MAX_FADE_TERMS - 1);
+            self.outgoing = Blend::weighted(others, 1.0, target.into(), playing.weight(target));
+            self.current = target;
+            self.progress = 0.0;
+        }
+
+        if self.progress < 1.0 {
+            self.progress = (self.progress + step).min(1.0);
+        }
+        self.blend()
+    }
+
+    fn blend(&self) -> Blend {
+        if self.progress >= 1.0 {
+            return self.current.into();
+        }
+        let angle = self.progress * FRAC_PI_2;
+        Blend::weighted(self.outgoing, angle.cos(), self.current.into(), angle.sin())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nih_plug::prelude::Enum;
+
+    /// Changing the algorithm mid-fade, even several times in a row, shouldn't make any
+    /// algorithm's weight jump.
+    #[test]
+    fn retargeting_mid_fade_is_continuous() {
+        let step = 0.01;
+        let targets = [
+            (0, DistortionAlgorithm::SoftClip),
+            (20, DistortionAlgorithm::HardClip),
+            (60, DistortionAlgorithm::SineFold),
+            (70, DistortionAlgorithm::Tube),
+            (75, DistortionAlgorithm::HardClip),
+            (80, DistortionAlgorithm::SoftClip),
+        ];
+        let mut fade = AlgorithmFade::new(DistortionAlgorithm::SoftClip);
+        let mut target = DistortionAlgorithm::SoftClip;
+        let mut previous = Blend::from(target);
+        for i in 0..300 {
+            if let Some(&(_, new_target)) = targets.iter().find(|(start, _)| *start == i) {
+                target = new_target;
+            }
+            let blend = fade.next(target, step);
+            for index in 0..DistortionAlgorithm::variants().len() {
+                let algorithm = DistortionAlgorithm::from_index(index);
+                let jump = (blend.weight(algorithm) - previous.weight(algorithm)).abs();
+                assert!(
+                    jump < 0.05,
+                    "algorithm {} jumped by {} at {}",
+                    index,
+                    jump,
+                    i
+                );
+            }
+            previous = blend;
+        }
+        assert_eq!(previous.weight(DistortionAlgorithm::SoftClip), 1.0);
+    }
+}
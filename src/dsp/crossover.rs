@@ -0,0 +1,142 @@
+use super::filter::{Biquad, BiquadCoefficients};
+
+pub const MAX_BANDS: usize = 4;
+pub const MAX_SPLITS: usize = MAX_BANDS - 1;
+/// Every band below a split except for the lowest split gets an allpass filter for that split.
+const MAX_ALLPASSES: usize = MAX_SPLITS * (MAX_SPLITS - 1) / 2;
+
+#[derive(Default, Clone, Copy)]
+pub struct SplitCoefficients {
+    lowpass: BiquadCoefficients,
+    highpass: BiquadCoefficients,
+    allpass: BiquadCoefficients,
+}
+
+impl SplitCoefficients {
+    pub fn new(sample_rate: f32, frequency: f32) -> Self {
+        Self {
+            lowpass: BiquadCoefficients::lowpass(sample_rate, frequency),
+            highpass: BiquadCoefficients::highpass(sample_rate, frequency),
+            allpass: BiquadCoefficients::allpass(sample_rate, frequency),
+        }
+    }
+}
+
+/// The coefficients for all of the crossover's splits, shared between the channels.
+#[derive(Default)]
+pub struct CrossoverCoefficients {
+    pub splits: [SplitCoefficients; MAX_SPLITS],
+}
+
+/// Splits a single channel into up to [`MAX_BANDS`] bands with fourth order Linkwitz-Riley
+/// filters. The bands sum back to an allpass filtered version of the input, so they can be
+/// recombined without any notches or bumps at the split frequencies.
+#[derive(Default)]
+pub struct Crossover {
+    splits: [LinkwitzRiley; MAX_SPLITS],
+    allpasses: [Biquad; MAX_ALLPASSES],
+}
+
+impl Crossover {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn split(
+        &mut self,
+        coefficients: &CrossoverCoefficients,
+        bands: usize,
+        input: f32,
+    ) -> [f32; MAX_BANDS] {
+        let mut output = [0.0; MAX_BANDS];
+        let mut rest = input;
+        for ((band, filter), c) in output
+            .iter_mut()
+            .zip(&mut self.splits)
+            .zip(&coefficients.splits)
+            .take(bands - 1)
+        {
+            let (low, high) = filter.split(c, rest);
+            *band = low;
+            rest = high;
+        }
+        output[bands - 1] = rest;
+
+        // The bands below a split have to go through that split's allpass to stay in phase with
+        // the bands above it
+        let mut allpasses = self.allpasses.iter_mut();
+        for (split, c) in coefficients
+            .splits
+            .iter()
+            .enumerate()
+            .take(bands - 1)
+            .skip(1)
+        {
+            for (band, allpass) in output[..split].iter_mut().zip(&mut allpasses) {
+                *band = allpass.process(&c.allpass, *band);
+            }
+        }
+        output
+    }
+}
+
+#[derive(Default)]
+struct LinkwitzRiley {
+    lowpass: [Biquad; 2],
+    highpass: [Biquad; 2],
+}
+
+impl LinkwitzRiley {
+    fn split(&mut self, coefficients: &SplitCoefficients, input: f32) -> (f32, f32) {
+        let low = self
+            .lowpass
+            .iter_mut()
+            .fold(input, |x, filter| filter.process(&coefficients.lowpass, x));
+        let high = self
+            .highpass
+            .iter_mut()
+            .fold(input, |x, filter| filter.process(&coefficients.highpass, x));
+        (low, high)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const SAMPLE_RATE: f32 = 44_100.0;
+
+    /// The bands should add back up to the input's level at every frequency, including right at
+    /// the splits.
+    #[test]
+    fn bands_sum_to_allpass() {
+        let coefficients = CrossoverCoefficients {
+            splits: [200.0, 1000.0, 5000.0].map(|f| SplitCoefficients::new(SAMPLE_RATE, f)),
+        };
+        for bands in 2..=MAX_BANDS {
+            for frequency in [50.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 12_000.0] {
+                let mut crossover = Crossover::default();
+                let mut input_level = 0.0;
+                let mut output_level = 0.0;
+                // A second after the filters settle, which is a whole number of periods
+                for i in 0..8192 + SAMPLE_RATE as usize {
+                    let input = (TAU * frequency * i as f32 / SAMPLE_RATE).sin();
+                    let output: f32 = crossover.split(&coefficients, bands, input).iter().sum();
+                    if i >= 8192 {
+                        input_level += input.powi(2);
+                        output_level += output.powi(2);
+                    }
+                }
+                let gain_db = 10.0 * (output_level / input_level).log10();
+                assert!(
+                    gain_db.abs() < 0.01,
+                    "{} bands are off by {} dB at {} Hz",
+                    bands,
+                    gain_db,
+                    frequency
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use nih_plug::prelude::Enum;
+
+use super::noise::NoiseRng;
+
+/// How far the hold time can stray from its nominal length with the jitter turned all the way up,
+/// as a fraction of that length.
+const MAX_JITTER: f32 = 0.5;
+
+/// Where the bitcrusher sits relative to the algorithm.
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum CrusherPosition {
+    #[name = "Before Algorithm"]
+    Pre,
+    #[name = "After Algorithm"]
+    Post,
+}
+
+/// The values a [`Crusher`] needs for a single sample.
+#[derive(Clone, Copy)]
+pub struct CrusherSettings {
+    pub enabled: bool,
+    pub position: CrusherPosition,
+    /// The resolution, which doesn't have to be a whole number.
+    pub bits: f32,
+    /// How many samples every held sample lasts on average.
+    pub downsample: f32,
+    /// How much the hold time varies randomly, from 0 to 1.
+    pub jitter: f32,
+    pub dither: bool,
+}
+
+impl CrusherSettings {
+    /// Whether the crusher is enabled and sits at this position.
+    pub fn is_at(&self, position: CrusherPosition) -> bool {
+        self.enabled && self.position == position
+    }
+}
+
+/// Bit depth reduction and sample-and-hold downsampling for a single channel.
+pub struct Crusher {
+    /// The number of samples since the held sample was taken.
+    phase: f32,
+    /// The number of samples until the next sample is taken.
+    period: f32,
+    held: f32,
+}
+
+impl Default for Crusher {
+    fn default() -> Self {
+        Self {
+            phase: 0.0,
+            period: 1.0,
+            held: 0.0,
+        }
+    }
+}
+
+impl Crusher {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn process(&mut self, settings: &CrusherSettings, input: f32, rng: &mut NoiseRng) -> f32 {
+        // The random numbers are drawn every sample to keep the noise generators in sync
+        let jitter = rng.bipolar(0.0) * settings.jitter * MAX_JITTER;
+        let dither = rng.unipolar(0.0) - rng.unipolar(0.0);
+
+        self.phase += 1.0;
+        if self.phase >= self.period {
+            self.phase -= self.period;
+            self.period = (settings.downsample * (1.0 + jitter)).max(1.0);
+            self.held = input;
+        }
+
+        let step = 0.5f32.powf(settings.bits - 1.0);
+        // Triangular dither of one step decorrelates the quantization error from the signal
+        let dither = if settings.dither { dither } else { 0.0 };
+        (self.held / step + dither).round() * step
+    }
+}
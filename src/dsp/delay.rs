@@ -0,0 +1,153 @@
+use std::f32::consts::PI;
+
+/// Enough for every stage of the chain at the highest oversampling, which is 109 samples, plus the
+/// samples the interpolation looks ahead of and behind the delay.
+const BUFFER_LENGTH: usize = 128;
+/// The length of the interpolation filter for delays that aren't a whole number of samples. This
+/// keeps the response flat to within a hundredth of a decibel up to 15 kHz at 44.1 kHz.
+const INTERPOLATION_TAPS: usize = 16;
+const MAX_DELAY: f32 = (BUFFER_LENGTH - INTERPOLATION_TAPS / 2 - 1) as f32;
+
+/// A short delay line for lining signals up with the latency of the shapers, which usually isn't a
+/// whole number of samples.
+pub struct DelayLine {
+    buffer: Vec<f32>,
+    /// Where the next sample gets written.
+    position: usize,
+    /// The delay the interpolation filter was last computed for.
+    delay: f32,
+    /// The delay of the filter's first tap in whole samples.
+    first_tap: usize,
+    taps: [f32; INTERPOLATION_TAPS],
+    tap_count: usize,
+}
+
+impl Default for DelayLine {
+    fn default() -> Self {
+        let mut taps = [0.0; INTERPOLATION_TAPS];
+        taps[0] = 1.0;
+        Self {
+            buffer: vec![0.0; BUFFER_LENGTH],
+            position: 0,
+            delay: 0.0,
+            first_tap: 0,
+            taps,
+            tap_count: 1,
+        }
+    }
+}
+
+impl DelayLine {
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+    }
+
+    /// Stores `input` and returns the signal from `delay` samples ago, where a delay of zero is the
+    /// input itself.
+    pub fn process(&mut self, input: f32, delay: f32) -> f32 {
+        if delay != self.delay {
+            self.update_taps(delay);
+        }
+        self.buffer[self.position] = input;
+        let output = self.taps[..self.tap_count]
+            .iter()
+            .enumerate()
+            .map(|(i, tap)| tap * self.delayed(self.first_tap + i))
+            .sum();
+        self.position = (self.position + 1) % BUFFER_LENGTH;
+        output
+    }
+
+    /// Computes a Blackman-windowed sinc filter centered on the delay. Short delays don't leave
+    /// room for the whole filter, so they get a shorter one.
+    fn update_taps(&mut self, delay: f32) {
+        self.delay = delay;
+        let delay = delay.clamp(0.0, MAX_DELAY);
+        let whole = delay.floor() as usize;
+        if delay == whole as f32 {
+            self.first_tap = whole;
+            self.taps[0] = 1.0;
+            self.tap_count = 1;
+            return;
+        }
+
+        let half = (INTERPOLATION_TAPS / 2).min(whole + 1);
+        self.first_tap = whole + 1 - half;
+        self.tap_count = 2 * half;
+        for (i, tap) in self.taps[..self.tap_count].iter_mut().enumerate() {
+            let t = (self.first_tap + i) as f32 - delay;
+            let sinc = (PI * t).sin() / (PI * t);
+            let phase = PI * t / half as f32;
+            let window = 0.42 + 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+            *tap = sinc * window;
+        }
+
+        // Unity gain at DC
+        let sum: f32 = self.taps[..self.tap_count].iter().sum();
+        for tap in &mut self.taps[..self.tap_count] {
+            *tap /= sum;
+        }
+    }
+
+    /// The sample written `delay` samples ago, where a delay of zero is the last one.
+    fn delayed(&self, delay: usize) -> f32 {
+        self.buffer[(self.position + BUFFER_LENGTH - delay) % BUFFER_LENGTH]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::oversampling::{Oversampler, Oversampling};
+
+    const SAMPLE_RATE: f32 = 44_100.0;
+
+    #[test]
+    fn whole_delays_are_exact() {
+        for delay in [0, 1, 7, 50, 100] {
+            let mut line = DelayLine::default();
+            let output: Vec<f32> = (0..120)
+                .map(|i| line.process(i as f32, delay as f32))
+                .collect();
+            for (i, sample) in output.iter().enumerate().skip(delay) {
+                assert_eq!(*sample, (i - delay) as f32);
+            }
+        }
+    }
+
+    /// The dry signal delayed by the oversampling's latency should cancel out a wet signal that
+    /// only went through the oversampler.
+    #[test]
+    fn nulls_against_oversampled_passthrough() {
+        for oversampling in [
+            Oversampling::X2,
+            Oversampling::X4,
+            Oversampling::X8,
+            Oversampling::X16,
+        ] {
+            for frequency in [100.0, 1_000.0, 5_000.0, 10_000.0, 15_000.0] {
+                let mut oversampler = Oversampler::default();
+                let mut line = DelayLine::default();
+                let mut difference = 0.0;
+                let mut level = 0.0;
+                for i in 0..4096 {
+                    let input = (2.0 * PI * frequency * i as f32 / SAMPLE_RATE).sin();
+                    let wet = oversampler.process(oversampling, input, |x| x);
+                    let dry = line.process(input, oversampling.latency());
+                    // Skip the filters filling up
+                    if i >= 256 {
+                        difference += (wet - dry).powi(2);
+                        level += dry.powi(2);
+                    }
+                }
+                let null_db = 10.0 * (difference / level).log10();
+                assert!(
+                    null_db < -50.0,
+                    "{} Hz only nulls to {} dB",
+                    frequency,
+                    null_db
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use nih_plug::prelude::Enum;
+
+/// What the sidechain's envelope modulates.
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum SidechainTarget {
+    Drive,
+    Mix,
+    Noise,
+}
+
+/// A peak envelope follower with separate attack and release times.
+#[derive(Default)]
+pub struct EnvelopeFollower {
+    envelope: f32,
+}
+
+impl EnvelopeFollower {
+    /// The smoothing coefficient for an attack or release time in milliseconds.
+    pub fn coefficient(sample_rate: f32, time_ms: f32) -> f32 {
+        if time_ms <= 0.0 {
+            0.0
+        } else {
+            (-1000.0 / (time_ms * sample_rate)).exp()
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+    }
+
+    pub fn process(&mut self, input: f32, attack: f32, release: f32) -> f32 {
+        let level = input.abs();
+        let coefficient = if level > self.envelope {
+            attack
+        } else {
+            release
+        };
+        self.envelope = level + coefficient * (self.envelope - level);
+        self.envelope
+    }
+}
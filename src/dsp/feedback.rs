@@ -0,0 +1,80 @@
+use super::tone::{ToneCoefficients, ToneFilter};
+use crate::algorithms::soft_clip;
+
+pub const MAX_FEEDBACK_DELAY_MS: f32 = 50.0;
+/// Long enough for the longest delay at 192 kHz. Higher sample rates get a shorter maximum delay.
+const BUFFER_LENGTH: usize = 9_602;
+/// Keeps DC from building up in the loop, which asymmetric algorithms would otherwise cause.
+pub const FEEDBACK_HIGHPASS: f32 = 40.0;
+
+/// The values a [`Feedback`] loop needs for a single sample.
+#[derive(Clone, Copy)]
+pub struct FeedbackSettings<'a> {
+    /// How much of the shaped signal is fed back, negative values inverting it.
+    pub amount: f32,
+    /// The delay in samples, which doesn't have to be a whole number.
+    pub delay: f32,
+    /// Filters the signal on its way back. The lowpass is the damping.
+    pub filter: &'a ToneCoefficients,
+}
+
+impl FeedbackSettings<'_> {
+    /// Converts a delay time to samples, keeping it within what the delay line can hold.
+    pub fn delay_samples(sample_rate: f32, delay_ms: f32) -> f32 {
+        (delay_ms * sample_rate / 1000.0).clamp(1.0, (BUFFER_LENGTH - 2) as f32)
+    }
+}
+
+/// A delay line that feeds the shaped signal back into the shaper's input for a single channel.
+pub struct Feedback {
+    buffer: Vec<f32>,
+    /// Where the next sample gets written.
+    position: usize,
+    filter: ToneFilter,
+}
+
+impl Default for Feedback {
+    fn default() -> Self {
+        Self {
+            buffer: vec![0.0; BUFFER_LENGTH],
+            position: 0,
+            filter: ToneFilter::default(),
+        }
+    }
+}
+
+impl Feedback {
+    pub fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.filter.reset();
+    }
+
+    /// The signal to add to the shaper's input.
+    pub fn read(&self, settings: &FeedbackSettings) -> f32 {
+        // Linear interpolation, so the delay time can be changed smoothly
+        let whole = settings.delay.floor();
+        let fraction = settings.delay - whole;
+        let newer = self.delayed(whole as usize);
+        let older = self.delayed(whole as usize + 1);
+        settings.amount * (newer + fraction * (older - newer))
+    }
+
+    /// Stores the shaped signal. It's soft clipped on the way in, which keeps the loop from running
+    /// away no matter how it's set up.
+    pub fn write(&mut self, settings: &FeedbackSettings, shaped: f32) {
+        let filtered = self.filter.process(settings.filter, shaped);
+        let sample = if filtered.is_finite() {
+            soft_clip(filtered)
+        } else {
+            self.filter.reset();
+            0.0
+        };
+        self.buffer[self.position] = sample;
+        self.position = (self.position + 1) % BUFFER_LENGTH;
+    }
+
+    /// The sample written `delay` samples ago, where a delay of one is the last one.
+    fn delayed(&self, delay: usize) -> f32 {
+        self.buffer[(self.position + BUFFER_LENGTH - delay) % BUFFER_LENGTH]
+    }
+}
@@ -0,0 +1,161 @@
+use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+/// Normalized coefficients for a second order IIR filter, based on the RBJ Audio EQ Cookbook.
+#[derive(Clone, Copy)]
+pub struct BiquadCoefficients {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+}
+
+impl Default for BiquadCoefficients {
+    /// A filter that passes the signal through unchanged.
+    fn default() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        }
+    }
+}
+
+impl BiquadCoefficients {
+    /// A Butterworth lowpass filter.
+    pub fn lowpass(sample_rate: f32, frequency: f32) -> Self {
+        let (cos, alpha) = Self::intermediates(sample_rate, frequency, FRAC_1_SQRT_2);
+        Self::normalized(
+            (1.0 - cos) / 2.0,
+            1.0 - cos,
+            (1.0 - cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    /// A Butterworth highpass filter.
+    pub fn highpass(sample_rate: f32, frequency: f32) -> Self {
+        let (cos, alpha) = Self::intermediates(sample_rate, frequency, FRAC_1_SQRT_2);
+        Self::normalized(
+            (1.0 + cos) / 2.0,
+            -(1.0 + cos),
+            (1.0 + cos) / 2.0,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    /// An allpass filter with the same phase response as a fourth order Linkwitz-Riley crossover.
+    pub fn allpass(sample_rate: f32, frequency: f32) -> Self {
+        let (cos, alpha) = Self::intermediates(sample_rate, frequency, FRAC_1_SQRT_2);
+        Self::normalized(
+            1.0 - alpha,
+            -2.0 * cos,
+            1.0 + alpha,
+            1.0 + alpha,
+            -2.0 * cos,
+            1.0 - alpha,
+        )
+    }
+
+    /// A shelving filter that boosts or cuts everything above `frequency` by `gain_db` decibels.
+    pub fn high_shelf(sample_rate: f32, frequency: f32, gain_db: f32) -> Self {
+        let a = 10f32.powf(gain_db / 40.0);
+        let (cos, alpha) = Self::intermediates(sample_rate, frequency, FRAC_1_SQRT_2);
+        let sqrt_alpha = 2.0 * a.sqrt() * alpha;
+        Self::normalized(
+            a * ((a + 1.0) + (a - 1.0) * cos + sqrt_alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+            a * ((a + 1.0) + (a - 1.0) * cos - sqrt_alpha),
+            (a + 1.0) - (a - 1.0) * cos + sqrt_alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos),
+            (a + 1.0) - (a - 1.0) * cos - sqrt_alpha,
+        )
+    }
+
+    /// Scales the filter's output by a constant gain.
+    pub fn with_gain(self, gain: f32) -> Self {
+        Self {
+            b0: self.b0 * gain,
+            b1: self.b1 * gain,
+            b2: self.b2 * gain,
+            ..self
+        }
+    }
+
+    /// The filter's gain at a frequency.
+    pub fn magnitude(&self, sample_rate: f32, frequency: f32) -> f32 {
+        let w = 2.0 * PI * frequency / sample_rate;
+        let (cos1, sin1) = (w.cos(), w.sin());
+        let (cos2, sin2) = ((2.0 * w).cos(), (2.0 * w).sin());
+        let numerator =
+            (self.b0 + self.b1 * cos1 + self.b2 * cos2).hypot(self.b1 * sin1 + self.b2 * sin2);
+        let denominator =
+            (1.0 + self.a1 * cos1 + self.a2 * cos2).hypot(self.a1 * sin1 + self.a2 * sin2);
+        numerator / denominator
+    }
+
+    fn intermediates(sample_rate: f32, frequency: f32, q: f32) -> (f32, f32) {
+        // Frequencies too close to Nyquist make the filters blow up
+        let frequency = frequency.min(sample_rate * 0.49);
+        let w0 = 2.0 * PI * frequency / sample_rate;
+        (w0.cos(), w0.sin() / (2.0 * q))
+    }
+
+    fn normalized(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+/// The state of a biquad filter in transposed direct form II. The coefficients are passed in
+/// separately so they can be shared between channels.
+#[derive(Default, Clone, Copy)]
+pub struct Biquad {
+    s1: f32,
+    s2: f32,
+}
+
+impl Biquad {
+    #[inline]
+    pub fn process(&mut self, coefficients: &BiquadCoefficients, input: f32) -> f32 {
+        let c = coefficients;
+        let output = c.b0 * input + self.s1;
+        self.s1 = c.b1 * input - c.a1 * output + self.s2;
+        self.s2 = c.b2 * input - c.a2 * output;
+        output
+    }
+}
+
+/// A first order highpass filter for removing DC offsets.
+#[derive(Default, Clone, Copy)]
+pub struct DcBlocker {
+    x1: f32,
+    y1: f32,
+}
+
+impl DcBlocker {
+    /// The feedback coefficient for a cutoff frequency. Gets closer to one the lower the frequency
+    /// is.
+    pub fn coefficient(sample_rate: f32, frequency: f32) -> f32 {
+        (-2.0 * PI * frequency / sample_rate).exp()
+    }
+
+    #[inline]
+    pub fn process(&mut self, coefficient: f32, input: f32) -> f32 {
+        let output = input - self.x1 + coefficient * self.y1;
+        self.x1 = input;
+        self.y1 = output;
+        output
+    }
+}
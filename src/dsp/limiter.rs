@@ -0,0 +1,229 @@
+use std::f32::consts::PI;
+
+use super::envelope::EnvelopeFollower;
+
+/// How far ahead the limiter looks, so it can turn down before a peak arrives.
+const LOOKAHEAD_MS: f32 = 1.5;
+const RELEASE_MS: f32 = 100.0;
+/// The true peak is estimated at four times the sample rate, like ITU-R BS.1770 does.
+const TRUE_PEAK_PHASES: usize = 4;
+const TRUE_PEAK_TAPS: usize = 12;
+/// The delay of the interpolation filters, which the audio has to be delayed by as well.
+const TRUE_PEAK_DELAY: usize = TRUE_PEAK_TAPS / 2;
+
+/// A stereo lookahead limiter that keeps the true peak, including the peaks between samples, below
+/// a ceiling.
+///
+/// The gain needed for every incoming sample is held for the length of the lookahead and then
+/// averaged over the lookahead, so the gain ramps down smoothly and reaches its lowest point
+/// exactly when the peak leaves the delay line.
+pub struct Limiter {
+    taps: [[f32; TRUE_PEAK_TAPS]; TRUE_PEAK_PHASES],
+    /// The most recent input for every channel, newest first.
+    history: [[f32; TRUE_PEAK_TAPS]; 2],
+    lookahead: usize,
+    release_coefficient: f32,
+    delay: Vec<[f32; 2]>,
+    delay_position: usize,
+    /// The gains needed for the last `lookahead + 1` samples.
+    targets: Vec<f32>,
+    target_position: usize,
+    held: f32,
+    released: f32,
+    /// The last `lookahead` released gains, which get averaged.
+    ramp: Vec<f32>,
+    ramp_position: usize,
+    ramp_sum: f64,
+    gain: f32,
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        let mut limiter = Self {
+            taps: true_peak_taps(),
+            history: [[0.0; TRUE_PEAK_TAPS]; 2],
+            lookahead: 1,
+            release_coefficient: 0.0,
+            delay: Vec::new(),
+            delay_position: 0,
+            targets: Vec::new(),
+            target_position: 0,
+            held: 1.0,
+            released: 1.0,
+            ramp: Vec::new(),
+            ramp_position: 0,
+            ramp_sum: 0.0,
+            gain: 1.0,
+        };
+        limiter.set_sample_rate(44100.0);
+        limiter
+    }
+}
+
+impl Limiter {
+    /// Resizes the buffers for a new sample rate. This allocates, so it shouldn't be called from
+    /// the audio thread.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.lookahead = ((sample_rate * LOOKAHEAD_MS / 1000.0).round() as usize).max(1);
+        self.release_coefficient = EnvelopeFollower::coefficient(sample_rate, RELEASE_MS);
+        self.delay = vec![[0.0; 2]; self.latency()];
+        self.targets = vec![1.0; self.lookahead + 1];
+        self.ramp = vec![1.0; self.lookahead];
+        self.reset();
+    }
+
+    /// The delay of the limited signal in samples.
+    pub fn latency(&self) -> usize {
+        self.lookahead - 1 + TRUE_PEAK_DELAY
+    }
+
+    pub fn reset(&mut self) {
+        self.history = [[0.0; TRUE_PEAK_TAPS]; 2];
+        self.delay.fill([0.0; 2]);
+        self.delay_position = 0;
+        self.targets.fill(1.0);
+        self.target_position = 0;
+        self.held = 1.0;
+        self.released = 1.0;
+        self.ramp.fill(1.0);
+        self.ramp_position = 0;
+        self.ramp_sum = self.ramp.len() as f64;
+        self.gain = 1.0;
+    }
+
+    /// The gain that was applied to the last sample.
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    pub fn process(&mut self, input: [f32; 2], ceiling: f32) -> [f32; 2] {
+        let peak = self.true_peak(input);
+        let target = if peak > ceiling { ceiling / peak } else { 1.0 };
+
+        // The lowest target over the hold window
+        let expired = std::mem::replace(&mut self.targets[self.target_position], target);
+        self.target_position = (self.target_position + 1) % self.targets.len();
+        if target <= self.held {
+            self.held = target;
+        } else if expired <= self.held {
+            self.held = self.targets.iter().copied().fold(1.0, f32::min);
+        }
+
+        self.released = if self.held < self.released {
+            self.held
+        } else {
+            self.held + self.release_coefficient * (self.released - self.held)
+        };
+
+        let expired = std::mem::replace(&mut self.ramp[self.ramp_position], self.released);
+        self.ramp_position = (self.ramp_position + 1) % self.ramp.len();
+        self.ramp_sum += self.released as f64 - expired as f64;
+        self.gain = (self.ramp_sum / self.ramp.len() as f64).min(1.0) as f32;
+
+        let delayed = std::mem::replace(&mut self.delay[self.delay_position], input);
+        self.delay_position = (self.delay_position + 1) % self.delay.len();
+        delayed.map(|sample| sample * self.gain)
+    }
+
+    /// The highest absolute value of both channels between the sample `TRUE_PEAK_DELAY` samples ago
+    /// and the one after it.
+    fn true_peak(&mut self, input: [f32; 2]) -> f32 {
+        let mut peak = 0.0f32;
+        for (history, sample) in self.history.iter_mut().zip(input) {
+            history.copy_within(..TRUE_PEAK_TAPS - 1, 1);
+            history[0] = sample;
+            for taps in &self.taps {
+                let interpolated: f32 = taps.iter().zip(history.iter()).map(|(a, b)| a * b).sum();
+                peak = peak.max(interpolated.abs());
+            }
+        }
+        peak
+    }
+}
+
+/// Hann-windowed sinc interpolation filters for each of the phases between two samples.
+fn true_peak_taps() -> [[f32; TRUE_PEAK_TAPS]; TRUE_PEAK_PHASES] {
+    let mut taps = [[0.0; TRUE_PEAK_TAPS]; TRUE_PEAK_PHASES];
+    for (phase, phase_taps) in taps.iter_mut().enumerate() {
+        for (j, tap) in phase_taps.iter_mut().enumerate() {
+            let t = j as f32 - TRUE_PEAK_DELAY as f32 + phase as f32 / TRUE_PEAK_PHASES as f32;
+            let sinc = if t == 0.0 {
+                1.0
+            } else {
+                (PI * t).sin() / (PI * t)
+            };
+            let window = 0.5 * (1.0 + (PI * t / TRUE_PEAK_DELAY as f32).cos());
+            *tap = sinc * window;
+        }
+        // Passes DC at unity gain
+        let sum: f32 = phase_taps.iter().sum();
+        phase_taps.iter_mut().for_each(|tap| *tap /= sum);
+    }
+    taps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::TAU;
+
+    const SAMPLE_RATE: f32 = 44_100.0;
+
+    /// Sudden bursts at different frequencies and levels, with a different one on each channel.
+    fn bursts(i: usize) -> [f32; 2] {
+        let level = [0.5, 4.0, 1.5, 10.0][(i / 3000) % 4];
+        let t = i as f32 / SAMPLE_RATE;
+        [
+            level * (TAU * 110.0 * t).sin(),
+            level * (TAU * 11_025.0 * t + 0.25).sin(),
+        ]
+    }
+
+    /// Uniform white noise from a small linear congruential generator, at an unreasonable level.
+    fn noise(state: &mut u32) -> [f32; 2] {
+        [0, 1].map(|_| {
+            *state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            8.0 * (*state as f32 / u32::MAX as f32 - 0.5)
+        })
+    }
+
+    #[test]
+    fn stays_under_ceiling() {
+        for ceiling in [1.0, 0.5, 0.1] {
+            let mut limiter = Limiter::default();
+            limiter.set_sample_rate(SAMPLE_RATE);
+            let mut state = 1;
+            for i in 0..48_000 {
+                let input = if i < 24_000 {
+                    bursts(i)
+                } else {
+                    noise(&mut state)
+                };
+                for sample in limiter.process(input, ceiling) {
+                    assert!(
+                        sample.abs() <= ceiling * 1.0001,
+                        "{} goes over a ceiling of {} at {}",
+                        sample,
+                        ceiling,
+                        i
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn quiet_signals_are_only_delayed() {
+        let mut limiter = Limiter::default();
+        limiter.set_sample_rate(SAMPLE_RATE);
+        let latency = limiter.latency();
+        // The first burst stays below the ceiling, even between samples
+        let quiet = |i| bursts(i).map(|x| x * 0.9);
+        for i in 0..2000 {
+            let output = limiter.process(quiet(i), 1.0);
+            if i >= latency {
+                assert_eq!(output, quiet(i - latency));
+            }
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use nih_plug::prelude::Enum;
+
+/// What the output listens to, for hearing exactly what the processing does to the signal.
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum ListenMode {
+    /// The mixed signal, as usual.
+    Off,
+    /// Only what the processing adds to or takes away from the dry signal.
+    Delta,
+    /// Only the wet part of the mix.
+    #[name = "Wet Solo"]
+    WetSolo,
+}
+
+impl ListenMode {
+    /// `dry` is the unprocessed input lined up with the mixed signal, and `wet` is the part of
+    /// `mixed` that didn't come from the dry signal. All of them are left/right pairs.
+    pub fn output(self, mixed: [f32; 2], dry: [f32; 2], wet: [f32; 2]) -> [f32; 2] {
+        match self {
+            ListenMode::Off => mixed,
+            ListenMode::Delta => [mixed[0] - dry[0], mixed[1] - dry[1]],
+            ListenMode::WetSolo => wet,
+        }
+    }
+}
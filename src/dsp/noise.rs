@@ -0,0 +1,215 @@
+use nih_plug::prelude::Enum;
+
+/// The loudest the noise gets with the noise parameter turned all the way up.
+const MAX_NOISE_LEVEL: f32 = 0.5;
+/// The average number of crackles per second.
+const CRACKLE_RATE: f32 = 40.0;
+/// How quickly a crackle dies down, per sample.
+const CRACKLE_DECAY: f32 = 0.7;
+/// Both channels' shared generators start from this seed, so they stay in lockstep.
+const SHARED_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum NoiseColor {
+    White,
+    Pink,
+    Brown,
+    /// Sparse clicks and pops, like a dusty record.
+    Crackle,
+}
+
+/// Where the noise enters the signal.
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum NoiseMode {
+    /// Added to the shaped signal.
+    Additive,
+    /// Modulates the shaped signal's amplitude, so the noise follows the signal.
+    Multiplicative,
+    /// Added before shaping, so the noise gets distorted along with the signal.
+    #[name = "Pre-Shaper"]
+    PreShaper,
+}
+
+/// The values a [`NoiseGenerator`] needs for a single sample.
+#[derive(Clone, Copy)]
+pub struct NoiseSettings {
+    pub color: NoiseColor,
+    pub mode: NoiseMode,
+    /// The noise parameter after modulation and gating, from 0 to 1.
+    pub amount: f32,
+    /// The chance of a crackle starting on any given sample.
+    pub crackle_probability: f32,
+    /// How similar the channels' noise is, from independent at 0 to identical at 1.
+    pub correlation: f32,
+}
+
+impl NoiseSettings {
+    pub fn crackle_probability(sample_rate: f32) -> f32 {
+        CRACKLE_RATE / sample_rate
+    }
+
+    /// The noise's amplitude. The amount is squared so the lower half of the range stays subtle.
+    fn level(&self) -> f32 {
+        self.amount * self.amount * MAX_NOISE_LEVEL
+    }
+}
+
+/// Colors white noise for a single channel. Every color is scaled to peak at roughly ±1.
+#[derive(Default)]
+pub struct NoiseGenerator {
+    /// Paul Kellet's economy pink noise filter.
+    pink: [f32; 3],
+    brown: f32,
+    crackle: f32,
+}
+
+impl NoiseGenerator {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Returns the next noise sample, already scaled by the noise level.
+    pub fn next(&mut self, settings: &NoiseSettings, rng: &mut NoiseRng) -> f32 {
+        let white = rng.bipolar(settings.correlation);
+        // The filters keep running with every color so switching colors doesn't click
+        self.pink[0] = 0.99765 * self.pink[0] + white * 0.0990460;
+        self.pink[1] = 0.96300 * self.pink[1] + white * 0.2965164;
+        self.pink[2] = 0.57000 * self.pink[2] + white * 1.0526913;
+        let pink = (self.pink.iter().sum::<f32>() + white * 0.1848) * 0.25;
+        // A leaky integrator, so the brown noise can't drift away
+        self.brown = (self.brown + 0.02 * white) / 1.02;
+        let brown = self.brown * 3.5;
+
+        // Both random numbers are drawn every sample to keep the shared generators in sync
+        let crackle_chance = rng.unipolar(settings.correlation);
+        let crackle_amplitude = 0.2 + 0.8 * rng.unipolar(settings.correlation);
+        self.crackle *= CRACKLE_DECAY;
+        if crackle_chance < settings.crackle_probability {
+            self.crackle = white.signum() * crackle_amplitude;
+        }
+
+        let noise = match settings.color {
+            NoiseColor::White => white,
+            NoiseColor::Pink => pink,
+            NoiseColor::Brown => brown,
+            NoiseColor::Crackle => self.crackle,
+        };
+        noise * settings.level()
+    }
+}
+
+/// The random numbers for a single channel's noise. Every channel has a generator of its own and
+/// one that produces the same numbers in both channels, and the correlation blends between the two.
+/// The channels are always left and right, even in mid/side mode.
+pub struct NoiseRng {
+    seed: u64,
+    own: Xorshift,
+    shared: Xorshift,
+}
+
+impl NoiseRng {
+    pub fn new(channel: usize) -> Self {
+        // Spreads the seeds out, since similar seeds give similar first numbers
+        let seed = (channel as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        Self {
+            seed,
+            own: Xorshift::new(seed),
+            shared: Xorshift::new(SHARED_SEED),
+        }
+    }
+
+    /// Starts the sequence over, so the same input produces the same noise.
+    pub fn reset(&mut self) {
+        self.own = Xorshift::new(self.seed);
+        self.shared = Xorshift::new(SHARED_SEED);
+    }
+
+    /// A number between -1 and 1. The mix keeps the noise's power the same at any correlation.
+    pub fn bipolar(&mut self, correlation: f32) -> f32 {
+        let shared = self.shared.next_f32() * 2.0 - 1.0;
+        let own = self.own.next_f32() * 2.0 - 1.0;
+        shared * correlation.sqrt() + own * (1.0 - correlation).sqrt()
+    }
+
+    /// A number between 0 and 1, taken from the shared generator with a chance equal to the
+    /// correlation.
+    pub fn unipolar(&mut self, correlation: f32) -> f32 {
+        let shared = self.shared.next_f32();
+        let own = self.own.next_f32();
+        if self.own.next_f32() < correlation {
+            shared
+        } else {
+            own
+        }
+    }
+}
+
+/// Marsaglia's xorshift64* generator, which is plenty random for noise and far cheaper than a
+/// cryptographic generator.
+struct Xorshift {
+    state: u64,
+}
+
+impl Xorshift {
+    fn new(seed: u64) -> Self {
+        // The state must never be zero
+        Self { state: seed.max(1) }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in `[0, 1)`, from the highest 24 bits.
+    fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::stereo::StereoMode;
+
+    /// Fully correlated noise should end up equally loud on both sides, no matter which channels
+    /// the signal is processed as.
+    #[test]
+    fn correlated_noise_stays_centered() {
+        for color in [
+            NoiseColor::White,
+            NoiseColor::Pink,
+            NoiseColor::Brown,
+            NoiseColor::Crackle,
+        ] {
+            let settings = NoiseSettings {
+                color,
+                mode: NoiseMode::Additive,
+                amount: 1.0,
+                crackle_probability: NoiseSettings::crackle_probability(44_100.0),
+                correlation: 1.0,
+            };
+            for stereo_mode in [StereoMode::LeftRight, StereoMode::MidSide] {
+                let mut generators = [NoiseGenerator::default(), NoiseGenerator::default()];
+                let mut rngs = [NoiseRng::new(0), NoiseRng::new(1)];
+                let mut power = [0.0; 2];
+                for i in 0..10_000 {
+                    let input = [(i as f32 * 0.01).sin(), 0.5 * (i as f32 * 0.03).sin()];
+                    let [a, b] = stereo_mode.encode(input);
+                    let [noise_a, noise_b] = stereo_mode
+                        .encode([0, 1].map(|channel| {
+                            generators[channel].next(&settings, &mut rngs[channel])
+                        }));
+                    let output = stereo_mode.decode([a + noise_a, b + noise_b]);
+                    for channel in 0..2 {
+                        power[channel] += (output[channel] - input[channel]).powi(2);
+                    }
+                }
+                assert!(power[0] > 0.0);
+                assert!((power[0] - power[1]).abs() < 1e-3 * power[0]);
+            }
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::f32::consts::PI;
+
+use nih_plug::prelude::Enum;
+
+/// Number of taps in each half-band filter. Half-band filters need `4k - 1` taps.
+const HALF_BAND_TAPS: usize = 31;
+/// Apart from the center tap every other tap is zero, so the filter is split into a branch with
+/// the non-zero taps and a branch that is just a delay.
+const BRANCH_TAPS: usize = HALF_BAND_TAPS.div_ceil(2);
+/// The delay of the center tap's branch at the lower of the two rates.
+const CENTER_DELAY: usize = (HALF_BAND_TAPS - 1) / 4;
+
+pub const MAX_STAGES: usize = 4;
+pub const MAX_FACTOR: usize = 1 << MAX_STAGES;
+
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum Oversampling {
+    #[name = "1x"]
+    X1,
+    #[name = "2x"]
+    X2,
+    #[name = "4x"]
+    X4,
+    #[name = "8x"]
+    X8,
+    #[name = "16x"]
+    X16,
+}
+
+impl Oversampling {
+    /// The number of 2x stages needed for this factor.
+    pub fn stages(self) -> usize {
+        self as usize
+    }
+
+    pub fn factor(self) -> usize {
+        1 << self.stages()
+    }
+
+    /// The delay added by the up- and downsampling filters in samples at the host's sample rate.
+    pub fn latency(self) -> f32 {
+        // Every stage delays by half the filter length at its own rate, once on the way up and
+        // once on the way down. The decimator feeds the newer of every two samples to the filter
+        // branch, which makes the way down one sample at the higher rate shorter.
+        (1..=self.stages())
+            .map(|stage| (HALF_BAND_TAPS - 2) as f32 / (1 << stage) as f32)
+            .sum()
+    }
+}
+
+/// Upsamples a single channel with a cascade of polyphase half-band filters, runs a function on
+/// every upsampled sample, and decimates the result back down again.
+pub struct Oversampler {
+    taps: [f32; BRANCH_TAPS],
+    upsamplers: [Upsampler; MAX_STAGES],
+    downsamplers: [Downsampler; MAX_STAGES],
+}
+
+impl Default for Oversampler {
+    fn default() -> Self {
+        Self {
+            taps: half_band_taps(),
+            upsamplers: Default::default(),
+            downsamplers: Default::default(),
+        }
+    }
+}
+
+impl Oversampler {
+    pub fn reset(&mut self) {
+        self.upsamplers = Default::default();
+        self.downsamplers = Default::default();
+    }
+
+    pub fn process(
+        &mut self,
+        oversampling: Oversampling,
+        input: f32,
+        mut f: impl FnMut(f32) -> f32,
+    ) -> f32 {
+        let stages = oversampling.stages();
+        let mut buffer = [0.0; MAX_FACTOR];
+        let mut scratch = [0.0; MAX_FACTOR];
+        buffer[0] = input;
+
+        let mut len = 1;
+        for upsampler in &mut self.upsamplers[..stages] {
+            for (pair, &sample) in scratch.chunks_exact_mut(2).zip(&buffer[..len]) {
+                pair.copy_from_slice(&upsampler.process(&self.taps, sample));
+            }
+            len *= 2;
+            buffer[..len].copy_from_slice(&scratch[..len]);
+        }
+
+        for sample in &mut buffer[..len] {
+            *sample = f(*sample);
+        }
+
+        // The last stage on the way up runs at the highest rate, so it pairs with the first stage
+        // on the way down
+        for downsampler in self.downsamplers[..stages].iter_mut().rev() {
+            for (sample, pair) in scratch.iter_mut().zip(buffer[..len].chunks_exact(2)) {
+                *sample = downsampler.process(&self.taps, [pair[0], pair[1]]);
+            }
+            len /= 2;
+            buffer[..len].copy_from_slice(&scratch[..len]);
+        }
+        buffer[0]
+    }
+}
+
+#[derive(Default)]
+struct Upsampler {
+    history: [f32; BRANCH_TAPS],
+}
+
+impl Upsampler {
+    /// Takes one sample and produces two at twice the rate.
+    fn process(&mut self, taps: &[f32; BRANCH_TAPS], input: f32) -> [f32; 2] {
+        self.history.copy_within(0..BRANCH_TAPS - 1, 1);
+        self.history[0] = input;
+        let even: f32 = taps.iter().zip(&self.history).map(|(t, x)| t * x).sum();
+        // Zero stuffing halves the level, so both branches get a gain of two
+        [2.0 * even, self.history[CENTER_DELAY]]
+    }
+}
+
+#[derive(Default)]
+struct Downsampler {
+    history: [f32; BRANCH_TAPS],
+    delay: [f32; CENTER_DELAY + 1],
+}
+
+impl Downsampler {
+    /// Takes two samples and produces one at half the rate.
+    fn process(&mut self, taps: &[f32; BRANCH_TAPS], input: [f32; 2]) -> f32 {
+        self.history.copy_within(0..BRANCH_TAPS - 1, 1);
+        self.history[0] = input[1];
+        self.delay.copy_within(0..CENTER_DELAY, 1);
+        self.delay[0] = input[0];
+        let odd: f32 = taps.iter().zip(&self.history).map(|(t, x)| t * x).sum();
+        odd + 0.5 * self.delay[CENTER_DELAY]
+    }
+}
+
+/// The non-zero taps of a Blackman-windowed half-band lowpass filter.
+fn half_band_taps() -> [f32; BRANCH_TAPS] {
+    let center = (HALF_BAND_TAPS / 2) as f32;
+    let mut taps = [0.0; BRANCH_TAPS];
+    for (i, tap) in taps.iter_mut().enumerate() {
+        let n = (2 * i) as f32;
+        let t = n - center;
+        let sinc = (PI * t / 2.0).sin() / (PI * t);
+        let phase = 2.0 * PI * (n + 1.0) / (HALF_BAND_TAPS + 1) as f32;
+        let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+        *tap = sinc * window;
+    }
+
+    // Each branch should have a DC gain of exactly one half
+    let sum: f32 = taps.iter().sum();
+    for tap in &mut taps {
+        *tap *= 0.5 / sum;
+    }
+    taps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latency_matches_impulse_response() {
+        for oversampling in [
+            Oversampling::X1,
+            Oversampling::X2,
+            Oversampling::X4,
+            Oversampling::X8,
+            Oversampling::X16,
+        ] {
+            let mut oversampler = Oversampler::default();
+            let response: Vec<f32> = (0..128)
+                .map(|i| oversampler.process(oversampling, if i == 0 { 1.0 } else { 0.0 }, |x| x))
+                .collect();
+            // The filters are linear phase, so the delay is the impulse response's center of mass
+            let sum: f32 = response.iter().sum();
+            let delay = response
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| i as f32 * sample)
+                .sum::<f32>()
+                / sum;
+            assert!((sum - 1.0).abs() < 1e-4);
+            assert!(
+                (delay - oversampling.latency()).abs() < 1e-3,
+                "{} samples measured, {} reported",
+                delay,
+                oversampling.latency()
+            );
+        }
+    }
+}
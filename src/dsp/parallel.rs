@@ -0,0 +1,91 @@
+use super::adaa::AntiAliasing;
+use super::delay::DelayLine;
+use super::oversampling::Oversampling;
+use super::shaper::Shaper;
+use super::tone::{ToneCoefficients, ToneFilter};
+use crate::algorithms::{AlgorithmContext, Blend, DistortionAlgorithm};
+
+/// The settings for the parallel path, shared by both channels.
+pub struct ParallelSettings {
+    pub algorithm: Blend,
+    pub drive: f32,
+    /// Filters the input before it's distorted.
+    pub filter: ToneCoefficients,
+    /// From only the main path at zero to only the parallel path at one.
+    pub balance: f32,
+    /// Where the paths end up in the stereo field, from -1 for left to 1 for right.
+    pub main_pan: f32,
+    pub parallel_pan: f32,
+    /// Lines the parallel path up with the main path's serial stages, in samples.
+    pub delay: f32,
+}
+
+impl Default for ParallelSettings {
+    fn default() -> Self {
+        Self {
+            algorithm: DistortionAlgorithm::SoftClip.into(),
+            drive: 1.0,
+            filter: ToneCoefficients::default(),
+            balance: 0.0,
+            main_pan: 0.0,
+            parallel_pan: 0.0,
+            delay: 0.0,
+        }
+    }
+}
+
+impl ParallelSettings {
+    /// Whether the parallel path can be heard. It's not processed otherwise, and its state is
+    /// cleared so it comes back in cleanly as the balance fades it in.
+    pub fn is_audible(&self) -> bool {
+        self.balance > 0.0
+    }
+
+    /// Pans both paths and balances them against each other. Both are left/right pairs.
+    pub fn combine(&self, main: [f32; 2], parallel: [f32; 2]) -> [f32; 2] {
+        let main = pan(main, self.main_pan);
+        let parallel = pan(parallel, self.parallel_pan);
+        [0, 1].map(|i| main[i] * (1.0 - self.balance) + parallel[i] * self.balance)
+    }
+}
+
+/// Turns down the side the signal is panned away from. The center leaves the signal untouched.
+fn pan([left, right]: [f32; 2], pan: f32) -> [f32; 2] {
+    [left * (1.0 - pan).min(1.0), right * (1.0 + pan).min(1.0)]
+}
+
+/// A second distortion path for a single channel, running next to the main one.
+#[derive(Default)]
+pub struct ParallelPath {
+    filter: ToneFilter,
+    shaper: Shaper,
+    delay: DelayLine,
+}
+
+impl ParallelPath {
+    pub fn reset(&mut self) {
+        self.filter.reset();
+        self.shaper.reset();
+        self.delay.reset();
+    }
+
+    pub fn process(
+        &mut self,
+        input: f32,
+        settings: &ParallelSettings,
+        context: AlgorithmContext,
+        oversampling: Oversampling,
+        anti_aliasing: AntiAliasing,
+    ) -> f32 {
+        let filtered = self.filter.process(&settings.filter, input);
+        let shaped = self.shaper.process(
+            filtered * settings.drive,
+            &settings.algorithm,
+            context,
+            0.0,
+            oversampling,
+            anti_aliasing,
+        );
+        self.delay.process(shaped, settings.delay)
+    }
+}
@@ -0,0 +1,61 @@
+use super::adaa::{Adaa, AdaaDelay, AntiAliasing};
+use super::analog::{Tape, Tube};
+use super::oversampling::{Oversampler, Oversampling};
+use crate::algorithms::{AlgorithmContext, Blend, DistortionAlgorithm};
+
+/// A single waveshaper with its own oversampling and anti-aliasing state.
+#[derive(Default)]
+pub struct Shaper {
+    oversampler: Oversampler,
+    adaa: Adaa,
+    /// Keeps the stateful algorithms lined up with the anti-aliased ones.
+    stateful_delay: AdaaDelay,
+    tube: Tube,
+    tape: Tape,
+}
+
+impl Shaper {
+    pub fn reset(&mut self) {
+        self.oversampler.reset();
+        self.adaa.reset();
+        self.stateful_delay.reset();
+        self.tube.reset();
+        self.tape.reset();
+    }
+
+    /// Shapes the input after shifting it by `bias`. The static offset the bias causes is removed
+    /// again, so silence stays silent.
+    ///
+    /// The stateful algorithms in the blend can't use antiderivative anti-aliasing, so they're
+    /// processed separately and rely on oversampling instead. They're delayed by as much as the
+    /// anti-aliasing delays the rest, so blending them doesn't cancel out the highs.
+    pub fn process(
+        &mut self,
+        input: f32,
+        blend: &Blend,
+        context: AlgorithmContext,
+        bias: f32,
+        oversampling: Oversampling,
+        anti_aliasing: AntiAliasing,
+    ) -> f32 {
+        let (adaa, stateful_delay) = (&mut self.adaa, &mut self.stateful_delay);
+        let (tube, tape) = (&mut self.tube, &mut self.tape);
+        let offset = blend.calculate(bias, context);
+        let stateless = blend.stateless();
+        let tube_weight = blend.weight(DistortionAlgorithm::Tube);
+        let tape_weight = blend.weight(DistortionAlgorithm::Tape);
+        self.oversampler.process(oversampling, input, |x| {
+            let x = x + bias;
+            let y = adaa.process(anti_aliasing, &stateless, context, x);
+            // The models only run while they're audible
+            let mut stateful = 0.0;
+            if tube_weight != 0.0 {
+                stateful += tube_weight * tube.process(x, context.analog);
+            }
+            if tape_weight != 0.0 {
+                stateful += tape_weight * tape.process(x, context.analog);
+            }
+            y + stateful_delay.process(anti_aliasing, stateful) - offset
+        })
+    }
+}
@@ -0,0 +1,36 @@
+use nih_plug::prelude::Enum;
+
+#[derive(PartialEq, Enum, Copy, Clone)]
+pub enum StereoMode {
+    /// Both channels share the main settings.
+    Linked,
+    /// The right channel uses its own settings.
+    #[name = "Left/Right"]
+    LeftRight,
+    /// The side signal uses its own settings.
+    #[name = "Mid/Side"]
+    MidSide,
+}
+
+impl StereoMode {
+    /// Whether the second channel uses its own settings instead of the main ones.
+    pub fn is_split(self) -> bool {
+        self != StereoMode::Linked
+    }
+
+    /// Turns a left/right pair into the two signals that get distorted.
+    pub fn encode(self, [left, right]: [f32; 2]) -> [f32; 2] {
+        match self {
+            StereoMode::MidSide => [0.5 * (left + right), 0.5 * (left - right)],
+            _ => [left, right],
+        }
+    }
+
+    /// The inverse of [`encode()`][Self::encode()].
+    pub fn decode(self, [a, b]: [f32; 2]) -> [f32; 2] {
+        match self {
+            StereoMode::MidSide => [a + b, a - b],
+            _ => [a, b],
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use super::filter::{Biquad, BiquadCoefficients};
+
+/// The frequency the tilt filter pivots around.
+const TILT_FREQUENCY: f32 = 800.0;
+/// The highpass is switched off at this frequency and below.
+pub const HIGHPASS_OFF: f32 = 20.0;
+/// The lowpass is switched off at this frequency and above.
+pub const LOWPASS_OFF: f32 = 20_000.0;
+
+/// The coefficients for a [`ToneFilter`], shared between the channels. These are only recomputed
+/// when the settings change.
+#[derive(Default)]
+pub struct ToneCoefficients {
+    highpass: Option<BiquadCoefficients>,
+    lowpass: Option<BiquadCoefficients>,
+    tilt: Option<BiquadCoefficients>,
+    /// The settings the coefficients were last computed for.
+    settings: Option<(f32, f32, f32, f32)>,
+}
+
+impl ToneCoefficients {
+    /// `tilt` is the difference in decibels between the highs and the lows. Filters that are set to
+    /// their neutral values are skipped entirely.
+    pub fn update(&mut self, sample_rate: f32, highpass: f32, lowpass: f32, tilt: f32) {
+        let settings = Some((sample_rate, highpass, lowpass, tilt));
+        if settings == self.settings {
+            return;
+        }
+        self.settings = settings;
+
+        self.highpass =
+            (highpass > HIGHPASS_OFF).then(|| BiquadCoefficients::highpass(sample_rate, highpass));
+        self.lowpass =
+            (lowpass < LOWPASS_OFF).then(|| BiquadCoefficients::lowpass(sample_rate, lowpass));
+        self.tilt = (tilt != 0.0).then(|| {
+            // Half of the tilt is taken off the lows and the other half is added to the highs
+            BiquadCoefficients::high_shelf(sample_rate, TILT_FREQUENCY, tilt)
+                .with_gain(10f32.powf(-tilt / 40.0))
+        });
+    }
+
+    /// The gain of the filters at a frequency, used for drawing the frequency response.
+    pub fn magnitude(&self, sample_rate: f32, frequency: f32) -> f32 {
+        [&self.highpass, &self.lowpass, &self.tilt]
+            .into_iter()
+            .flatten()
+            .map(|coefficients| coefficients.magnitude(sample_rate, frequency))
+            .product()
+    }
+}
+
+/// A highpass, a lowpass and a tilt filter in series.
+#[derive(Default)]
+pub struct ToneFilter {
+    highpass: Biquad,
+    lowpass: Biquad,
+    tilt: Biquad,
+}
+
+impl ToneFilter {
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn process(&mut self, coefficients: &ToneCoefficients, input: f32) -> f32 {
+        [
+            (&mut self.highpass, &coefficients.highpass),
+            (&mut self.lowpass, &coefficients.lowpass),
+            (&mut self.tilt, &coefficients.tilt),
+        ]
+        .into_iter()
+        .fold(input, |x, (filter, coefficients)| match coefficients {
+            Some(coefficients) => filter.process(coefficients, x),
+            None => x,
+        })
+    }
+}
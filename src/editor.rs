@@ -1,29 +1,65 @@
 use atomic_float::AtomicF32;
-use nih_plug::prelude::Editor;
+use nih_plug::prelude::{Editor, Enum, EnumParam, FloatParam, Param};
 use nih_plug::util;
 use nih_plug_vizia::vizia::prelude::*;
-use nih_plug_vizia::widgets::ResizeHandle;
+use nih_plug_vizia::widgets::param_base::ParamWidgetBase;
+use nih_plug_vizia::widgets::{ParamEvent, ResizeHandle};
 use nih_plug_vizia::{create_vizia_editor, ViziaState, ViziaTheming};
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+mod crossover;
 mod enum_button;
+mod filter_response;
+mod gain_meter;
 mod graph;
 mod knob;
 mod peak_meter;
+mod symmetry_button;
+mod toggle_button;
 
+use crate::algorithms::DistortionAlgorithm;
 use crate::data::UIData;
+use crate::dsp::auto_gain::MAX_COMPENSATION_DB;
+use crate::dsp::crossover::MAX_BANDS;
+use crate::dsp::oversampling::Oversampling;
 use crate::DistortoiseParams;
 
+use self::crossover::CrossoverDisplay;
 use self::enum_button::EnumButton;
+use self::filter_response::FilterResponse;
+use self::gain_meter::GainMeter;
 use self::graph::{DistortionGraph, GraphBackground};
-use self::knob::Knob;
+use self::knob::{Knob, ModulationSource};
 use self::peak_meter::{PeakMeter, PeakMeterOutline};
+use self::symmetry_button::SymmetryButton;
+use self::toggle_button::ToggleButton;
 
 pub const AFRICAN_TTF: &[u8] = include_bytes!("../assets/African-l6d.ttf");
 pub const AFRICAN: &str = "African";
 
+/// The lowest point on the limiter's gain reduction meter.
+const MAX_LIMITER_GAIN_REDUCTION_DB: f32 = 12.0;
+
+/// The button labels for the algorithms, in the same order as [`DistortionAlgorithm`].
+const ALGORITHM_NAMES: [&str; 14] = [
+    "Softclip",
+    "Hardclip",
+    "Radial",
+    "Chomper",
+    "Sine",
+    "Stepper",
+    "Humpback",
+    "Absolute",
+    "Custom",
+    "Tri Fold",
+    "Sine Fold",
+    "Buchla Fold",
+    "Tube",
+    "Tape",
+];
+
 #[derive(Lens)]
 struct Data {
     params: Arc<DistortoiseParams>,
@@ -35,7 +71,7 @@ struct Data {
 impl Model for Data {}
 
 pub(crate) fn default_state() -> Arc<ViziaState> {
-    ViziaState::new(|| (850, 450))
+    ViziaState::new(|| (850, 580))
 }
 
 pub(crate) fn create(
@@ -80,7 +116,7 @@ pub(crate) fn create(
                             .class("peak-meter");
                             ZStack::new(cx, |cx| {
                                 GraphBackground::new(cx);
-                                DistortionGraph::new(cx, Data::ui_data);
+                                DistortionGraph::new(cx, Data::ui_data, Data::params);
                             })
                             .class("graph");
                             ZStack::new(cx, |cx| {
@@ -95,93 +131,193 @@ pub(crate) fn create(
                                 PeakMeterOutline::new(cx).class("peak-meter");
                             })
                             .class("peak-meter");
+                            GainMeter::new(
+                                cx,
+                                Data::ui_data
+                                    .map(|ui_data| ui_data.lock().unwrap().get_auto_gain()),
+                                -MAX_COMPENSATION_DB,
+                                MAX_COMPENSATION_DB,
+                            )
+                            .class("gain-meter");
+                            GainMeter::new(
+                                cx,
+                                Data::ui_data
+                                    .map(|ui_data| ui_data.lock().unwrap().get_limiter_gain()),
+                                -MAX_LIMITER_GAIN_REDUCTION_DB,
+                                0.0,
+                            )
+                            .class("gain-meter");
                         })
                         .bottom(Pixels(10.0))
                         .space(Stretch(1.0));
 
                         HStack::new(cx, |cx| {
-                            Knob::new(cx, Data::params, |p| &p.noise, false);
-                            Knob::new(cx, Data::params, |p| &p.drive, false).class("drive");
-                            Knob::new(cx, Data::params, |p| &p.mix, false).class("drive");
-                            Knob::new(cx, Data::params, |p| &p.gain, false);
-                        })
-                        .top(Pixels(20.0));
-                    });
-                    HStack::new(cx, |cx| {
-                        VStack::new(cx, |cx| {
-                            EnumButton::new(
+                            let params = Data::params.get(cx);
+                            let ui_data = Data::ui_data.get(cx);
+                            Knob::new_modulated(
                                 cx,
                                 Data::params,
-                                |p| &p.algorithm,
-                                "Softclip".to_string(),
-                                0,
-                                7,
+                                |p| &p.noise,
+                                false,
+                                modulation(&params, &ui_data, |p| &p.noise, UIData::get_noise),
                             );
-                            EnumButton::new(
+                            Knob::new(cx, Data::params, |p| &p.envelope_depth, true);
+                            Knob::new_modulated(
                                 cx,
                                 Data::params,
-                                |p| &p.algorithm,
-                                "Hardclip".to_string(),
-                                1,
-                                7,
-                            );
-                            EnumButton::new(
-                                cx,
-                                Data::params,
-                                |p| &p.algorithm,
-                                "Radial".to_string(),
-                                2,
-                                7,
-                            );
-                            EnumButton::new(
-                                cx,
-                                Data::params,
-                                |p| &p.algorithm,
-                                "Chomper".to_string(),
-                                3,
-                                7,
-                            );
-                        });
-                        VStack::new(cx, |cx| {
-                            EnumButton::new(
+                                |p| &p.drive,
+                                false,
+                                modulation(&params, &ui_data, |p| &p.drive, UIData::get_drive),
+                            )
+                            .class("drive");
+                            Knob::new(cx, Data::params, |p| &p.bias, true);
+                            Knob::new(cx, Data::params, |p| &p.morph, false);
+                            Knob::new_modulated(
                                 cx,
                                 Data::params,
-                                |p| &p.algorithm,
-                                "Sine".to_string(),
-                                4,
-                                7,
-                            );
-                            EnumButton::new(
-                                cx,
-                                Data::params,
-                                |p| &p.algorithm,
-                                "Stepper".to_string(),
-                                5,
-                                7,
-                            );
-                            EnumButton::new(
-                                cx,
-                                Data::params,
-                                |p| &p.algorithm,
-                                "Humpback".to_string(),
-                                6,
-                                7,
-                            );
-                            EnumButton::new(
-                                cx,
-                                Data::params,
-                                |p| &p.algorithm,
-                                "Absolute".to_string(),
-                                7,
-                                7,
-                            );
-                        });
+                                |p| &p.mix,
+                                false,
+                                modulation(&params, &ui_data, |p| &p.mix, UIData::get_mix),
+                            )
+                            .class("drive");
+                            Knob::new(cx, Data::params, |p| &p.gain, false);
+                        })
+                        .top(Pixels(20.0));
+                    });
+                    VStack::new(cx, |cx| {
+                        Label::new(cx, "Algorithm A").class("algorithm-label");
+                        algorithm_menu(cx, |p| &p.algorithm);
+                        Label::new(cx, "Algorithm B").class("algorithm-label");
+                        algorithm_menu(cx, |p| &p.morph_algorithm);
+                        SymmetryButton::new(cx, Data::params).class("symmetry-button");
+                        Label::new(cx, "Oversampling").class("algorithm-label");
+                        oversampling_menu(cx);
+                        HStack::new(cx, |cx| {
+                            ToggleButton::new(cx, Data::params, |p| &p.auto_gain, "Auto Gain");
+                            ToggleButton::new(cx, Data::params, |p| &p.limiter, "Limiter");
+                        })
+                        .class("option-row")
+                        .top(Pixels(10.0));
                     })
                     .class("algorithm-menu");
                 })
                 .class("body");
+                HStack::new(cx, |cx| {
+                    band_count_menu(cx);
+                    VStack::new(cx, |cx| {
+                        CrossoverDisplay::new(cx, Data::params).class("crossover-display");
+                        band_buttons(cx);
+                    })
+                    .class("crossover");
+                    FilterResponse::new(cx, Data::params, Data::ui_data).class("filter-response");
+                })
+                .class("bottom-row");
             });
         })
         .class("main");
     })
 }
+
+/// Two columns of buttons, one for each algorithm.
+fn algorithm_menu<FMap>(cx: &mut Context, params_to_param: FMap)
+where
+    FMap: Fn(&Arc<DistortoiseParams>) -> &EnumParam<DistortionAlgorithm> + Copy + 'static,
+{
+    let rows = ALGORITHM_NAMES.len().div_ceil(2);
+    let last_id = ALGORITHM_NAMES.len() as i32 - 1;
+    HStack::new(cx, |cx| {
+        for (column, names) in ALGORITHM_NAMES.chunks(rows).enumerate() {
+            VStack::new(cx, |cx| {
+                for (row, name) in names.iter().enumerate() {
+                    EnumButton::new(
+                        cx,
+                        Data::params,
+                        params_to_param,
+                        name.to_string(),
+                        (column * rows + row) as i32,
+                        last_id,
+                    );
+                }
+            });
+        }
+    });
+}
+
+/// A row of buttons, one for each oversampling factor.
+fn oversampling_menu(cx: &mut Context) {
+    let last_id = Oversampling::variants().len() as i32 - 1;
+    HStack::new(cx, |cx| {
+        for (id, name) in Oversampling::variants().iter().enumerate() {
+            EnumButton::new(
+                cx,
+                Data::params,
+                |p| &p.oversampling,
+                name.to_string(),
+                id as i32,
+                last_id,
+            );
+        }
+    })
+    .class("option-row");
+}
+
+/// A column of buttons for the number of bands.
+fn band_count_menu(cx: &mut Context) {
+    VStack::new(cx, |cx| {
+        Label::new(cx, "Bands").class("band-count-label");
+        for bands in 1..=MAX_BANDS {
+            EnumButton::new(
+                cx,
+                Data::params,
+                |p| &p.bands,
+                bands.to_string(),
+                bands as i32 - 1,
+                MAX_BANDS as i32 - 1,
+            );
+        }
+    })
+    .class("band-count");
+}
+
+/// Solo, mute and bypass buttons for every band that's in use, from the lowest band up.
+fn band_buttons(cx: &mut Context) {
+    HStack::new(cx, |cx| {
+        for band in 0..MAX_BANDS {
+            HStack::new(cx, |cx| {
+                Label::new(cx, &(band + 1).to_string()).class("band-label");
+                ToggleButton::new(cx, Data::params, move |p| &p.band_params[band].solo, "S");
+                ToggleButton::new(cx, Data::params, move |p| &p.band_params[band].mute, "M");
+                ToggleButton::new(cx, Data::params, move |p| &p.band_params[band].bypass, "B");
+            })
+            .class("band")
+            .display(ParamWidgetBase::make_lens(
+                Data::params,
+                |p| &p.bands,
+                move |bands| bands.value() as usize > band,
+            ));
+        }
+    })
+    .class("band-buttons");
+}
+
+/// Lets the host know the custom curve changed by flipping
+/// [`custom_curve_edited`][DistortoiseParams::custom_curve_edited].
+fn custom_curve_edited(cx: &mut EventContext, params: &DistortoiseParams) {
+    let param = &params.custom_curve_edited;
+    let flipped = if param.value() { 0.0 } else { 1.0 };
+    cx.emit(ParamEvent::BeginSetParameter(param).upcast());
+    cx.emit(ParamEvent::SetParameterNormalized(param, flipped).upcast());
+    cx.emit(ParamEvent::EndSetParameter(param).upcast());
+}
+
+/// Shows the value the audio thread last used for a parameter on its knob.
+fn modulation(
+    params: &Arc<DistortoiseParams>,
+    ui_data: &Arc<Mutex<UIData>>,
+    param: fn(&DistortoiseParams) -> &FloatParam,
+    get_value: fn(&UIData) -> f32,
+) -> ModulationSource {
+    let params = params.clone();
+    let ui_data = ui_data.clone();
+    Arc::new(move || param(&params).preview_normalized(get_value(&ui_data.lock().unwrap())))
+}
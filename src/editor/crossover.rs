@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use nih_plug::prelude::Param;
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::vizia::vg;
+use nih_plug_vizia::widgets::ParamEvent;
+
+use crate::DistortoiseParams;
+
+const MIN_FREQUENCY: f32 = 20.0;
+const MAX_FREQUENCY: f32 = 20_000.0;
+/// How close to a split the mouse has to be to grab it, in pixels.
+const GRAB_DISTANCE: f32 = 8.0;
+
+/// Shows the crossover frequencies on a logarithmic frequency axis. The splits can be dragged
+/// around with the mouse.
+pub struct CrossoverDisplay {
+    params: Arc<DistortoiseParams>,
+    dragged_split: Option<usize>,
+}
+
+impl CrossoverDisplay {
+    pub fn new<LParams>(cx: &mut Context, params: LParams) -> Handle<Self>
+    where
+        LParams: Lens<Target = Arc<DistortoiseParams>>,
+    {
+        Self {
+            params: params.get(cx),
+            dragged_split: None,
+        }
+        .build(cx, |_cx| ())
+    }
+
+    fn active_splits(&self) -> usize {
+        self.params.bands.value() as usize - 1
+    }
+}
+
+impl View for CrossoverDisplay {
+    fn element(&self) -> Option<&'static str> {
+        Some("crossover-display")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                let bounds = cx.bounds();
+                let mouse_x = cx.mouse.cursorx;
+                self.dragged_split = self
+                    .params
+                    .crossovers
+                    .iter()
+                    .take(self.active_splits())
+                    .map(|crossover| {
+                        (frequency_to_x(bounds, crossover.frequency.value()) - mouse_x).abs()
+                    })
+                    .enumerate()
+                    .filter(|(_, distance)| *distance < GRAB_DISTANCE)
+                    .min_by(|(_, a), (_, b)| a.total_cmp(b))
+                    .map(|(split, _)| split);
+
+                if let Some(split) = self.dragged_split {
+                    let param = &self.params.crossovers[split].frequency;
+                    cx.capture();
+                    cx.set_active(true);
+                    cx.emit(ParamEvent::BeginSetParameter(param).upcast());
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if let Some(split) = self.dragged_split.take() {
+                    let param = &self.params.crossovers[split].frequency;
+                    cx.emit(ParamEvent::EndSetParameter(param).upcast());
+                    cx.release();
+                    cx.set_active(false);
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseMove(x, _y) => {
+                if let Some(split) = self.dragged_split {
+                    let param = &self.params.crossovers[split].frequency;
+                    let frequency = x_to_frequency(cx.bounds(), *x);
+                    cx.emit(
+                        ParamEvent::SetParameterNormalized(
+                            param,
+                            param.preview_normalized(frequency),
+                        )
+                        .upcast(),
+                    );
+                }
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let paint = vg::Paint::color(cx.background_color().cloned().unwrap_or_default().into());
+
+        // Background color
+        let mut background_path = vg::Path::new();
+        background_path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(
+            &mut background_path,
+            &vg::Paint::color(vg::Color::rgb(255, 186, 73)),
+        );
+
+        // A grid line for every decade
+        let mut grid_path = vg::Path::new();
+        for frequency in [100.0, 1_000.0, 10_000.0] {
+            let x = frequency_to_x(bounds, frequency);
+            grid_path.move_to(x, bounds.y);
+            grid_path.line_to(x, bounds.y + bounds.h);
+        }
+        canvas.stroke_path(&mut grid_path, &paint);
+
+        // The splits, thicker than the grid so they're easy to grab
+        let mut split_paint = paint.clone();
+        split_paint.set_line_width(3.0);
+        let mut split_path = vg::Path::new();
+        for crossover in self.params.crossovers.iter().take(self.active_splits()) {
+            let x = frequency_to_x(bounds, crossover.frequency.value());
+            split_path.move_to(x, bounds.y);
+            split_path.line_to(x, bounds.y + bounds.h);
+        }
+        canvas.stroke_path(&mut split_path, &split_paint);
+
+        // Outline
+        let mut outline_paint = paint;
+        outline_paint.set_line_width(2.0);
+        let mut outline_path = vg::Path::new();
+        outline_path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.stroke_path(&mut outline_path, &outline_paint);
+    }
+}
+
+fn frequency_to_x(bounds: BoundingBox, frequency: f32) -> f32 {
+    let normalized = (frequency / MIN_FREQUENCY).ln() / (MAX_FREQUENCY / MIN_FREQUENCY).ln();
+    bounds.x + normalized.clamp(0.0, 1.0) * bounds.w
+}
+
+fn x_to_frequency(bounds: BoundingBox, x: f32) -> f32 {
+    let normalized = ((x - bounds.x) / bounds.w).clamp(0.0, 1.0);
+    MIN_FREQUENCY * (MAX_FREQUENCY / MIN_FREQUENCY).powf(normalized)
+}
@@ -0,0 +1,119 @@
+use std::sync::{Arc, Mutex};
+
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::vizia::vg;
+
+use crate::data::UIData;
+use crate::dsp::tone::{ToneCoefficients, HIGHPASS_OFF, LOWPASS_OFF};
+use crate::params::ToneParams;
+use crate::DistortoiseParams;
+
+const RESPONSE_RESOLUTION: i32 = 200;
+/// The range of the vertical axis in both directions, in decibels.
+const DB_RANGE: f32 = 24.0;
+
+/// Shows the frequency response of the pre-filter and the post-filter. The post-filter's response
+/// goes underneath.
+pub struct FilterResponse {
+    params: Arc<DistortoiseParams>,
+    ui_data: Arc<Mutex<UIData>>,
+}
+
+impl FilterResponse {
+    pub fn new<LParams, LUIData>(
+        cx: &mut Context,
+        params: LParams,
+        ui_data: LUIData,
+    ) -> Handle<Self>
+    where
+        LParams: Lens<Target = Arc<DistortoiseParams>>,
+        LUIData: Lens<Target = Arc<Mutex<UIData>>>,
+    {
+        Self {
+            params: params.get(cx),
+            ui_data: ui_data.get(cx),
+        }
+        .build(cx, |_cx| ())
+    }
+}
+
+impl View for FilterResponse {
+    fn element(&self) -> Option<&'static str> {
+        Some("filter-response")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let paint = vg::Paint::color(cx.background_color().cloned().unwrap_or_default().into());
+        let sample_rate = self.ui_data.lock().unwrap().get_sample_rate();
+
+        // Background color
+        let mut background_path = vg::Path::new();
+        background_path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(
+            &mut background_path,
+            &vg::Paint::color(vg::Color::rgb(255, 186, 73)),
+        );
+
+        // The 0 dB line
+        let mut grid_path = vg::Path::new();
+        grid_path.move_to(bounds.x, bounds.y + 0.5 * bounds.h);
+        grid_path.line_to(bounds.x + bounds.w, bounds.y + 0.5 * bounds.h);
+        canvas.stroke_path(&mut grid_path, &paint);
+
+        let pre_tilt = self.params.pre_filter.tilt.value();
+        let de_emphasis = if self.params.de_emphasis.value() {
+            pre_tilt
+        } else {
+            0.0
+        };
+        let post = coefficients(&self.params.post_filter, sample_rate, -de_emphasis);
+        let pre = coefficients(&self.params.pre_filter, sample_rate, 0.0);
+
+        let mut post_paint = vg::Paint::color(vg::Color::rgb(34, 177, 76));
+        post_paint.set_line_width(2.0);
+        canvas.stroke_path(&mut response_path(bounds, &post, sample_rate), &post_paint);
+        let mut pre_paint = paint.clone();
+        pre_paint.set_line_width(2.0);
+        canvas.stroke_path(&mut response_path(bounds, &pre, sample_rate), &pre_paint);
+
+        // Outline
+        let mut outline_paint = paint;
+        outline_paint.set_line_width(2.0);
+        let mut outline_path = vg::Path::new();
+        outline_path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.stroke_path(&mut outline_path, &outline_paint);
+    }
+}
+
+fn coefficients(params: &ToneParams, sample_rate: f32, extra_tilt: f32) -> ToneCoefficients {
+    let mut coefficients = ToneCoefficients::default();
+    coefficients.update(
+        sample_rate,
+        params.highpass.value(),
+        params.lowpass.value(),
+        params.tilt.value() + extra_tilt,
+    );
+    coefficients
+}
+
+fn response_path(
+    bounds: BoundingBox,
+    coefficients: &ToneCoefficients,
+    sample_rate: f32,
+) -> vg::Path {
+    let mut path = vg::Path::new();
+    for i in 0..=RESPONSE_RESOLUTION {
+        let position = i as f32 / RESPONSE_RESOLUTION as f32;
+        let frequency = HIGHPASS_OFF * (LOWPASS_OFF / HIGHPASS_OFF).powf(position);
+        let db = 20.0 * coefficients.magnitude(sample_rate, frequency).log10();
+        let x = bounds.x + position * bounds.w;
+        let y = bounds.y + 0.5 * bounds.h * (1.0 - (db / DB_RANGE).clamp(-1.0, 1.0));
+        if i == 0 {
+            path.move_to(x, y);
+        } else {
+            path.line_to(x, y);
+        }
+    }
+    path
+}
@@ -0,0 +1,75 @@
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::vizia::vg;
+
+/// A vertical bar showing a gain change in decibels. The bar grows from the 0 dB line, which sits
+/// wherever 0 dB falls in the meter's range.
+pub struct GainMeter<L>
+where
+    L: Lens<Target = f32>,
+{
+    gain_db: L,
+    min_db: f32,
+    max_db: f32,
+}
+
+impl<L> GainMeter<L>
+where
+    L: Lens<Target = f32>,
+{
+    pub fn new(cx: &mut Context, gain_db: L, min_db: f32, max_db: f32) -> Handle<Self> {
+        Self {
+            gain_db,
+            min_db,
+            max_db,
+        }
+        .build(cx, |_cx| ())
+    }
+
+    /// The distance from the bottom of the meter as a fraction of its height.
+    fn position(&self, db: f32) -> f32 {
+        ((db - self.min_db) / (self.max_db - self.min_db)).clamp(0.0, 1.0)
+    }
+}
+
+impl<L> View for GainMeter<L>
+where
+    L: Lens<Target = f32>,
+{
+    fn element(&self) -> Option<&'static str> {
+        Some("gain-meter")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let paint = vg::Paint::color(cx.background_color().cloned().unwrap_or_default().into());
+        let y = |db| bounds.y + bounds.h * (1.0 - self.position(db));
+
+        // Background color
+        let mut background_path = vg::Path::new();
+        background_path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.fill_path(
+            &mut background_path,
+            &vg::Paint::color(vg::Color::rgb(255, 186, 73)),
+        );
+
+        // Gain bar
+        let (zero_y, gain_y) = (y(0.0), y(self.gain_db.get(cx)));
+        let mut bar_path = vg::Path::new();
+        bar_path.rect(
+            bounds.x,
+            zero_y.min(gain_y),
+            bounds.w,
+            (zero_y - gain_y).abs(),
+        );
+        canvas.fill_path(&mut bar_path, &paint);
+
+        // The 0 dB line and the outline
+        let mut line_paint = paint;
+        line_paint.set_line_width(2.0);
+        let mut line_path = vg::Path::new();
+        line_path.move_to(bounds.x, zero_y);
+        line_path.line_to(bounds.x + bounds.w, zero_y);
+        line_path.rect(bounds.x, bounds.y, bounds.w, bounds.h);
+        canvas.stroke_path(&mut line_path, &line_paint);
+    }
+}
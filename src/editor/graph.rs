@@ -4,22 +4,77 @@ use std::sync::Mutex;
 use nih_plug_vizia::vizia::prelude::*;
 use nih_plug_vizia::vizia::vg;
 
+use super::custom_curve_edited;
+use crate::algorithms::custom::CustomCurve;
+use crate::algorithms::fold::FoldSettings;
+use crate::algorithms::{AlgorithmContext, Blend, DistortionAlgorithm};
 use crate::data::UIData;
+use crate::dsp::analog::AnalogCoefficients;
+use crate::DistortoiseParams;
+
+const CURVE_RESOLUTION: i32 = 1000;
+/// The radius of the custom curve's control points, in pixels.
+const POINT_RADIUS: f32 = 4.0;
+/// How close to a control point the mouse has to be to grab it, in pixels.
+const GRAB_DISTANCE: f32 = 8.0;
+
+/// Draws the transfer curve. When one of the algorithms is set to custom, the custom curve can be
+/// edited here as well: clicking adds a control point, dragging moves one, and right clicking
+/// removes one.
 pub struct DistortionGraph {
     ui_data: Arc<Mutex<UIData>>,
+    params: Arc<DistortoiseParams>,
+    dragged_point: Option<usize>,
 }
 pub struct GraphBackground {}
 
 impl DistortionGraph {
-    pub fn new<LUIData>(cx: &mut Context, ui_data: LUIData) -> Handle<Self>
+    pub fn new<LUIData, LParams>(
+        cx: &mut Context,
+        ui_data: LUIData,
+        params: LParams,
+    ) -> Handle<Self>
     where
         LUIData: Lens<Target = Arc<Mutex<UIData>>>,
+        LParams: Lens<Target = Arc<DistortoiseParams>>,
     {
         Self {
             ui_data: ui_data.get(cx),
+            params: params.get(cx),
+            dragged_point: None,
         }
         .build(cx, |_cx| ())
     }
+
+    fn editing_custom_curve(&self) -> bool {
+        self.params.algorithm.value() == DistortionAlgorithm::Custom
+            || self.params.morph_algorithm.value() == DistortionAlgorithm::Custom
+    }
+
+    /// The control point under the mouse, if there is one.
+    fn point_at(&self, bounds: BoundingBox, position: (f32, f32)) -> Option<usize> {
+        let curve = self.params.custom_curve.read().unwrap();
+        let (x, y) = curve.editable_point(position);
+        curve
+            .points()
+            .iter()
+            .map(|&(point_x, point_y)| {
+                // In pixels, so the grab distance is the same in both directions
+                ((point_x - x) * bounds.w / 2.0).hypot((point_y - y) * bounds.h / 2.0)
+            })
+            .enumerate()
+            .filter(|(_, distance)| *distance < GRAB_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index)
+    }
+
+    /// Runs `f` on the custom curve and updates the audio thread's lookup table afterwards.
+    fn edit_curve<T>(&self, f: impl FnOnce(&mut CustomCurve) -> T) -> T {
+        let mut curve = self.params.custom_curve.write().unwrap();
+        let result = f(&mut curve);
+        self.params.custom_table.update(&curve);
+        result
+    }
 }
 
 impl View for DistortionGraph {
@@ -27,30 +82,180 @@ impl View for DistortionGraph {
         Some("distortion-graph")
     }
 
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| {
+            if !self.editing_custom_curve() {
+                return;
+            }
+            let bounds = cx.bounds();
+            let position = to_curve(bounds, (cx.mouse.cursorx, cx.mouse.cursory));
+            match window_event {
+                WindowEvent::MouseDown(MouseButton::Left) => {
+                    self.dragged_point = match self.point_at(bounds, position) {
+                        Some(index) => Some(index),
+                        None => self.edit_curve(|curve| curve.insert(position)),
+                    };
+                    if self.dragged_point.is_some() {
+                        cx.capture();
+                        cx.set_active(true);
+                        cx.needs_redraw();
+                        meta.consume();
+                    }
+                }
+                WindowEvent::MouseDown(MouseButton::Right) => {
+                    if let Some(index) = self.point_at(bounds, position) {
+                        self.edit_curve(|curve| curve.remove(index));
+                        custom_curve_edited(cx, &self.params);
+                        cx.needs_redraw();
+                        meta.consume();
+                    }
+                }
+                WindowEvent::MouseUp(MouseButton::Left) => {
+                    // A whole drag only counts as a single edit
+                    if self.dragged_point.take().is_some() {
+                        custom_curve_edited(cx, &self.params);
+                        cx.release();
+                        cx.set_active(false);
+                        meta.consume();
+                    }
+                }
+                WindowEvent::MouseMove(_, _) => {
+                    if let Some(index) = self.dragged_point {
+                        self.edit_curve(|curve| curve.move_point(index, position));
+                        cx.needs_redraw();
+                    }
+                }
+                _ => {}
+            }
+        });
+    }
+
     fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
-        const RESOLUTION: i32 = 1000;
         let data = self.ui_data.lock().unwrap();
-        let algorithm = data.get_algorithm();
-        let drive = data.get_drive();
         let bounds = cx.bounds();
-        let mut path = vg::Path::new();
-        let paint = vg::Paint::color(cx.background_color().cloned().unwrap_or_default().into());
-        path.move_to(bounds.x, bounds.y + bounds.h);
-        for i in 0..=RESOLUTION {
-            let next_point = (
-                bounds.x + i as f32 * bounds.w / RESOLUTION as f32,
-                bounds.y
-                    + 0.5
-                        * bounds.h
-                        * (1.0
-                            - algorithm
-                                .calculate(drive * (-1.0 + i as f32 * 2.0 / RESOLUTION as f32))),
+        let custom = self.params.custom_table.read();
+        let context = AlgorithmContext {
+            custom: &custom,
+            fold: FoldSettings {
+                folds: self.params.folds.value(),
+                symmetry: self.params.fold_symmetry.value(),
+            },
+            // Only the stateful algorithms use these, and the graph shows their static curves
+            analog: &AnalogCoefficients::default(),
+        };
+
+        // The rest of the chain shapes the main curve further. The filters between the stages
+        // don't change the static curve, so they're left out.
+        let stages: Vec<(Blend, f32)> = self
+            .params
+            .stage_order
+            .value()
+            .active_indices(self.params.stages.value() as usize)
+            .map(|index| {
+                let stage = &self.params.stage_params[index];
+                (stage.algorithm.value().into(), stage.drive.value())
+            })
+            .collect();
+
+        // The right or side channel's curve goes underneath the main one
+        let bias = data.get_bias();
+        let blend = |algorithm: DistortionAlgorithm| {
+            Blend::morph(
+                algorithm.into(),
+                data.get_morph_algorithm().into(),
+                data.get_morph(),
+            )
+        };
+        if data.get_split() {
+            let mut path = curve_path(
+                bounds,
+                &blend(data.get_algorithm_b()),
+                context,
+                data.get_drive_b(),
+                bias,
+                &stages,
             );
-            path.line_to(next_point.0, next_point.1);
-            path.move_to(next_point.0, next_point.1);
+            canvas.fill_path(&mut path, &vg::Paint::color(vg::Color::rgb(34, 177, 76)));
         }
+
+        let mut path = curve_path(
+            bounds,
+            &blend(data.get_algorithm()),
+            context,
+            data.get_drive(),
+            bias,
+            &stages,
+        );
+        let paint = vg::Paint::color(cx.background_color().cloned().unwrap_or_default().into());
         canvas.fill_path(&mut path, &paint);
+
+        // The custom curve itself, without drive, bias or morphing, along with its control points
+        if self.editing_custom_curve() {
+            let custom_paint = vg::Paint::color(vg::Color::rgb(34, 177, 76));
+            let mut path = curve_path(
+                bounds,
+                &DistortionAlgorithm::Custom.into(),
+                context,
+                1.0,
+                0.0,
+                &[],
+            );
+            canvas.fill_path(&mut path, &custom_paint);
+
+            let mut points_path = vg::Path::new();
+            for point in self.params.custom_curve.read().unwrap().control_points() {
+                let (x, y) = from_curve(bounds, point);
+                points_path.circle(x, y, POINT_RADIUS);
+            }
+            canvas.fill_path(&mut points_path, &custom_paint);
+            canvas.stroke_path(&mut points_path, &paint);
+        }
+    }
+}
+
+/// Converts a position in the graph to the custom curve's input and output.
+fn to_curve(bounds: BoundingBox, (x, y): (f32, f32)) -> (f32, f32) {
+    (
+        (x - bounds.x) / bounds.w * 2.0 - 1.0,
+        1.0 - (y - bounds.y) / bounds.h * 2.0,
+    )
+}
+
+fn from_curve(bounds: BoundingBox, (x, y): (f32, f32)) -> (f32, f32) {
+    (
+        bounds.x + (x + 1.0) / 2.0 * bounds.w,
+        bounds.y + (1.0 - y) / 2.0 * bounds.h,
+    )
+}
+
+/// The curve for `blend`, followed by the `(algorithm, drive)` of every later stage in the chain.
+fn curve_path(
+    bounds: BoundingBox,
+    blend: &Blend,
+    context: AlgorithmContext,
+    drive: f32,
+    bias: f32,
+    stages: &[(Blend, f32)],
+) -> vg::Path {
+    // The same offset the shaper removes, so the curve always goes through the center
+    let offset = blend.calculate(bias, context);
+    let mut path = vg::Path::new();
+    path.move_to(bounds.x, bounds.y + bounds.h);
+    for i in 0..=CURVE_RESOLUTION {
+        let x = drive * (-1.0 + i as f32 * 2.0 / CURVE_RESOLUTION as f32) + bias;
+        let y = stages
+            .iter()
+            .fold(blend.calculate(x, context) - offset, |y, (stage, drive)| {
+                stage.calculate(drive * y, context)
+            });
+        let next_point = (
+            bounds.x + i as f32 * bounds.w / CURVE_RESOLUTION as f32,
+            bounds.y + 0.5 * bounds.h * (1.0 - y),
+        );
+        path.line_to(next_point.0, next_point.1);
+        path.move_to(next_point.0, next_point.1);
     }
+    path
 }
 
 impl GraphBackground {
@@ -1,4 +1,5 @@
 use std::f32::consts::PI;
+use std::sync::Arc;
 
 use nih_plug::prelude::Param;
 use nih_plug_vizia::vizia::prelude::*;
@@ -11,6 +12,9 @@ use crate::editor::AFRICAN;
 static DRAG_SCALAR: f32 = 0.0042;
 static MODIFIER_SCALAR: f32 = 0.04;
 
+/// Returns the normalized value a parameter is currently modulated to.
+pub type ModulationSource = Arc<dyn Fn() -> f32>;
+
 pub struct DragStatus {
     modified: bool,
     start_value: f32,
@@ -30,6 +34,40 @@ impl Knob {
         params_to_param: FMap,
         centered: bool,
     ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: Fn(&Params) -> &P + Copy + 'static,
+    {
+        Self::build_knob(cx, params, params_to_param, centered, None)
+    }
+
+    /// A knob that also shows where the parameter is modulated to, for parameters that are
+    /// modulated inside of the plugin.
+    pub fn new_modulated<L, Params, P, FMap>(
+        cx: &mut Context,
+        params: L,
+        params_to_param: FMap,
+        centered: bool,
+        modulation: ModulationSource,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: Fn(&Params) -> &P + Copy + 'static,
+    {
+        Self::build_knob(cx, params, params_to_param, centered, Some(modulation))
+    }
+
+    fn build_knob<L, Params, P, FMap>(
+        cx: &mut Context,
+        params: L,
+        params_to_param: FMap,
+        centered: bool,
+        modulation: Option<ModulationSource>,
+    ) -> Handle<Self>
     where
         L: Lens<Target = Params> + Clone,
         Params: 'static,
@@ -52,7 +90,10 @@ impl Knob {
                             .make_lens(|param| param.unmodulated_normalized_value());
                         Binding::new(cx, param_lens, move |cx, param_lens| {
                             KnobReactive::new(cx, param_lens.get_val(cx), centered);
-                        })
+                        });
+                        if let Some(modulation) = &modulation {
+                            ModulationIndicator::new(cx, modulation.clone());
+                        }
                     })
                     .class("knob-graphic");
                     Label::new(cx, param_data.param().name())
@@ -163,6 +204,38 @@ impl View for KnobStatic {
     }
 }
 
+/// A dot on the knob's edge showing the modulated value.
+pub struct ModulationIndicator {
+    modulation: ModulationSource,
+}
+
+impl ModulationIndicator {
+    pub fn new(cx: &mut Context, modulation: ModulationSource) -> Handle<Self> {
+        Self { modulation }.build(cx, |_cx| ())
+    }
+}
+
+impl View for ModulationIndicator {
+    fn element(&self) -> Option<&'static str> {
+        Some("knob-modulation")
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        let radius = bounds.h / 2.0;
+        let normalized_value = (self.modulation)().clamp(0.0, 1.0);
+        let angle = (-150.0 + 300.0 * normalized_value).to_radians() - PI / 2.0;
+        let mut path = Path::new();
+        path.circle(
+            bounds.center().0 + angle.cos() * radius,
+            bounds.center().1 + angle.sin() * radius,
+            radius / 8.0,
+        );
+        let paint = Paint::color(cx.background_color().cloned().unwrap_or_default().into());
+        canvas.fill_path(&mut path, &paint);
+    }
+}
+
 pub struct KnobReactive {
     angle_start: f32,
     angle_end: f32,
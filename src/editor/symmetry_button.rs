@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use nih_plug_vizia::vizia::prelude::*;
+
+use super::custom_curve_edited;
+use super::enum_button::Border;
+use crate::editor::AFRICAN;
+use crate::DistortoiseParams;
+
+/// Switches the custom curve between being mirrored around the origin and being drawn freely.
+pub struct SymmetryButton {
+    params: Arc<DistortoiseParams>,
+}
+
+impl SymmetryButton {
+    pub fn new<LParams>(cx: &mut Context, params: LParams) -> Handle<Self>
+    where
+        LParams: Lens<Target = Arc<DistortoiseParams>>,
+    {
+        Self {
+            params: params.get(cx),
+        }
+        .build(cx, |cx| {
+            ZStack::new(cx, |cx| {
+                Label::new(cx, "Symmetric Curve")
+                    .font_family(vec![FamilyOwned::Name(String::from(AFRICAN))])
+                    .top(Stretch(1.0))
+                    .bottom(Stretch(1.0))
+                    .hoverable(false);
+                Border::new(cx);
+            });
+        })
+        // Follows the curve, which loading a preset can replace
+        .checked(params.map(|params| params.custom_curve.read().unwrap().symmetric()))
+    }
+}
+
+impl View for SymmetryButton {
+    fn element(&self) -> Option<&'static str> {
+        Some("symmetry-button")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                let mut curve = self.params.custom_curve.write().unwrap();
+                let symmetric = !curve.symmetric();
+                curve.set_symmetric(symmetric);
+                self.params.custom_table.update(&curve);
+                drop(curve);
+                custom_curve_edited(cx, &self.params);
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+}
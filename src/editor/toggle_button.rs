@@ -0,0 +1,75 @@
+use nih_plug::prelude::Param;
+use nih_plug_vizia::vizia::prelude::*;
+use nih_plug_vizia::widgets::param_base::ParamWidgetBase;
+
+use super::enum_button::Border;
+use crate::editor::AFRICAN;
+
+/// A button that switches a boolean parameter on and off.
+pub struct ToggleButton {
+    param_base: ParamWidgetBase,
+}
+
+impl ToggleButton {
+    pub fn new<L, Params, P, FMap>(
+        cx: &mut Context,
+        params: L,
+        params_to_param: FMap,
+        label: &str,
+    ) -> Handle<Self>
+    where
+        L: Lens<Target = Params> + Clone,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: Fn(&Params) -> &P + Copy + 'static,
+    {
+        let label = label.to_string();
+        Self {
+            param_base: ParamWidgetBase::new(cx, params.clone(), params_to_param),
+        }
+        .build(
+            cx,
+            ParamWidgetBase::build_view(params.clone(), params_to_param, move |cx, _param_data| {
+                ZStack::new(cx, |cx| {
+                    Label::new(cx, &label)
+                        .font_family(vec![FamilyOwned::Name(String::from(AFRICAN))])
+                        .top(Stretch(1.0))
+                        .bottom(Stretch(1.0))
+                        .hoverable(false);
+                    Border::new(cx);
+                });
+            }),
+        )
+        .checked(ParamWidgetBase::make_lens(
+            params,
+            params_to_param,
+            |param| param.modulated_normalized_value() >= 0.5,
+        ))
+    }
+
+    fn toggle(&self, cx: &mut EventContext) {
+        let on = self.param_base.unmodulated_normalized_value() >= 0.5;
+        self.param_base.begin_set_parameter(cx);
+        self.param_base
+            .set_normalized_value(cx, if on { 0.0 } else { 1.0 });
+        self.param_base.end_set_parameter(cx);
+    }
+}
+
+impl View for ToggleButton {
+    fn element(&self) -> Option<&'static str> {
+        Some("toggle-button")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match window_event {
+            WindowEvent::MouseDown(MouseButton::Left)
+            | WindowEvent::MouseDoubleClick(MouseButton::Left)
+            | WindowEvent::MouseTripleClick(MouseButton::Left) => {
+                self.toggle(cx);
+                meta.consume();
+            }
+            _ => {}
+        });
+    }
+}
@@ -1,17 +1,38 @@
+use algorithms::fold::FoldSettings;
+use algorithms::AlgorithmContext;
+use channel::{BandSettings, ChannelSettings, ChannelState, MultibandSettings};
 use data::UIData;
+use dsp::adaa::AntiAliasing;
+use dsp::analog::AnalogCoefficients;
+use dsp::auto_gain::AutoGain;
+use dsp::chain::{ChainSettings, MAX_EXTRA_STAGES};
+use dsp::crossfade::AlgorithmFade;
+use dsp::crossover::{SplitCoefficients, MAX_BANDS, MAX_SPLITS};
+use dsp::crusher::CrusherSettings;
+use dsp::delay::DelayLine;
+use dsp::envelope::{EnvelopeFollower, SidechainTarget};
+use dsp::feedback::{FeedbackSettings, FEEDBACK_HIGHPASS};
+use dsp::filter::DcBlocker;
+use dsp::limiter::Limiter;
+use dsp::noise::NoiseSettings;
+use dsp::oversampling::Oversampling;
+use dsp::parallel::ParallelSettings;
+use dsp::tone::ToneCoefficients;
 use nih_plug::prelude::*;
-use rand::{rngs::StdRng, Rng, SeedableRng};
 use std::sync::{Arc, Mutex};
 
 mod algorithms;
+mod channel;
 mod data;
+mod dsp;
 mod editor;
 mod params;
 
 use params::DistortoiseParams;
 
 const PEAK_METER_DECAY_MS: f64 = 150.0;
-const MAX_NOISE_VOLUME: f32 = 0.05;
+/// The most the envelope follower can multiply or divide the drive by.
+const MAX_ENVELOPE_DRIVE: f32 = 4.0;
 
 struct Distortoise {
     params: Arc<DistortoiseParams>,
@@ -19,18 +40,110 @@ struct Distortoise {
     peak_meter_decay_weight: f32,
     pre_peak_meter: Arc<AtomicF32>,
     peak_meter: Arc<AtomicF32>,
-    rng: StdRng,
+    sample_rate: f32,
+    channels: [ChannelState; 2],
+    /// Delay the left and right dry signals by as much as the shapers delay the wet signal, so
+    /// mixing them doesn't comb filter.
+    dry_delays: [DelayLine; 2],
+    oversampling: Oversampling,
+    anti_aliasing: AntiAliasing,
+    /// Whether the limiter was enabled in the last buffer, as it changes the latency.
+    limiter_enabled: bool,
+    /// Whether the transport was playing in the last buffer.
+    playing: bool,
+    multiband: MultibandSettings,
+    /// The frequencies the crossover coefficients were last computed for.
+    crossover_frequencies: [f32; MAX_SPLITS],
+    /// The frequency the DC blocker's coefficient was last computed for.
+    dc_block_frequency: f32,
+    dc_block_coefficient: f32,
+    algorithm_fades: AlgorithmFades,
+    chain: ChainSettings,
+    parallel: ParallelSettings,
+    pre_filter: ToneCoefficients,
+    post_filter: ToneCoefficients,
+    analog: AnalogCoefficients,
+    feedback_filter: ToneCoefficients,
+    envelope: EnvelopeFollower,
+    sidechain_envelope: EnvelopeFollower,
+    auto_gain: AutoGain,
+    limiter: Limiter,
+    /// The values after the envelope follower was applied, for the editor.
+    modulated_values: ModulatedValues,
+}
+
+#[derive(Default)]
+struct ModulatedValues {
+    drive: f32,
+    mix: f32,
+    noise: f32,
+    drive_b: f32,
+    /// The auto gain compensation that was applied, or unity when it's disabled.
+    auto_gain: f32,
+}
+
+/// A crossfade for every algorithm parameter, so changing them doesn't click.
+struct AlgorithmFades {
+    algorithm: AlgorithmFade,
+    morph_algorithm: AlgorithmFade,
+    algorithm_b: AlgorithmFade,
+    bands: [AlgorithmFade; MAX_BANDS],
+    stages: [AlgorithmFade; MAX_EXTRA_STAGES],
+    parallel: AlgorithmFade,
+}
+
+impl AlgorithmFades {
+    /// Starts out at the current parameter values without fading.
+    fn new(params: &DistortoiseParams) -> Self {
+        Self {
+            algorithm: AlgorithmFade::new(params.algorithm.value()),
+            morph_algorithm: AlgorithmFade::new(params.morph_algorithm.value()),
+            algorithm_b: AlgorithmFade::new(params.channel_b.algorithm.value()),
+            bands: params
+                .band_params
+                .each_ref()
+                .map(|band| AlgorithmFade::new(band.algorithm.value())),
+            stages: params
+                .stage_params
+                .each_ref()
+                .map(|stage| AlgorithmFade::new(stage.algorithm.value())),
+            parallel: AlgorithmFade::new(params.parallel.algorithm.value()),
+        }
+    }
 }
 
 impl Default for Distortoise {
     fn default() -> Self {
+        let params = Arc::new(DistortoiseParams::default());
         Self {
-            params: Arc::new(DistortoiseParams::default()),
+            algorithm_fades: AlgorithmFades::new(&params),
+            chain: ChainSettings::default(),
+            parallel: ParallelSettings::default(),
+            params,
             ui_data: Arc::new(Mutex::new(UIData::default())),
             peak_meter_decay_weight: 1.0,
             peak_meter: Arc::new(AtomicF32::new(util::MINUS_INFINITY_DB)),
             pre_peak_meter: Arc::new(AtomicF32::new(util::MINUS_INFINITY_DB)),
-            rng: StdRng::seed_from_u64(0),
+            sample_rate: 1.0,
+            channels: [ChannelState::new(0), ChannelState::new(1)],
+            dry_delays: Default::default(),
+            oversampling: Oversampling::X1,
+            anti_aliasing: AntiAliasing::Off,
+            limiter_enabled: false,
+            playing: false,
+            multiband: MultibandSettings::default(),
+            crossover_frequencies: [0.0; MAX_SPLITS],
+            dc_block_frequency: 0.0,
+            dc_block_coefficient: 0.0,
+            pre_filter: ToneCoefficients::default(),
+            post_filter: ToneCoefficients::default(),
+            analog: AnalogCoefficients::default(),
+            feedback_filter: ToneCoefficients::default(),
+            envelope: EnvelopeFollower::default(),
+            sidechain_envelope: EnvelopeFollower::default(),
+            auto_gain: AutoGain::default(),
+            limiter: Limiter::default(),
+            modulated_values: ModulatedValues::default(),
         }
     }
 }
@@ -47,10 +160,13 @@ impl Plugin for Distortoise {
         main_input_channels: NonZeroU32::new(2),
         main_output_channels: NonZeroU32::new(2),
 
-        aux_input_ports: &[],
+        aux_input_ports: &[new_nonzero_u32(2)],
         aux_output_ports: &[],
 
-        names: PortNames::const_default(),
+        names: PortNames {
+            aux_inputs: &["Sidechain"],
+            ..PortNames::const_default()
+        },
     }];
 
     const MIDI_INPUT: MidiConfig = MidiConfig::None;
@@ -79,42 +195,301 @@ impl Plugin for Distortoise {
         &mut self,
         _audio_io_layout: &AudioIOLayout,
         buffer_config: &BufferConfig,
-        _context: &mut impl InitContext<Self>,
+        context: &mut impl InitContext<Self>,
     ) -> bool {
         self.peak_meter_decay_weight = 0.25f64
             .powf((buffer_config.sample_rate as f64 * PEAK_METER_DECAY_MS / 1000.0).recip())
             as f32;
+        self.sample_rate = buffer_config.sample_rate;
+        self.ui_data
+            .lock()
+            .unwrap()
+            .set_sample_rate(self.sample_rate);
+        self.auto_gain.set_sample_rate(self.sample_rate);
+        self.limiter.set_sample_rate(self.sample_rate);
+        // Forces the crossover and DC blocker coefficients to be recomputed for the new sample rate
+        self.crossover_frequencies = [0.0; MAX_SPLITS];
+        self.dc_block_frequency = 0.0;
+        self.oversampling = self.params.oversampling.value();
+        self.anti_aliasing = self.params.anti_aliasing.value();
+        self.limiter_enabled = self.params.limiter.value();
+        self.chain.stages = self.params.stages.value() as usize;
+        context.set_latency_samples(self.latency_samples());
+        algorithms::prepare_antiderivative_tables();
+        // The curve may have been replaced when the plugin's state was loaded
+        self.params
+            .custom_table
+            .update(&self.params.custom_curve.read().unwrap());
         true
     }
 
-    fn reset(&mut self) {}
+    fn reset(&mut self) {
+        self.reset_channels();
+        self.envelope.reset();
+        self.sidechain_envelope.reset();
+        self.auto_gain.reset();
+        self.limiter.reset();
+        if self.params.noise_reset.value() {
+            self.reset_noise();
+        }
+        self.algorithm_fades = AlgorithmFades::new(&self.params);
+    }
 
     fn process(
         &mut self,
         buffer: &mut Buffer,
-        _aux: &mut AuxiliaryBuffers,
-        _context: &mut impl ProcessContext<Self>,
+        aux: &mut AuxiliaryBuffers,
+        context: &mut impl ProcessContext<Self>,
     ) -> ProcessStatus {
-        for channel_samples in buffer.iter_samples() {
-            let gain = self.params.gain.smoothed.next();
-            let drive = self.params.drive.smoothed.next();
-            let noise = self.params.noise.smoothed.next();
-            let algorithm = self.params.algorithm.value();
-            let mix = self.params.mix.smoothed.next();
+        let oversampling = self.params.oversampling.value();
+        let anti_aliasing = self.params.anti_aliasing.value();
+        if oversampling != self.oversampling || anti_aliasing != self.anti_aliasing {
+            self.oversampling = oversampling;
+            self.anti_aliasing = anti_aliasing;
+            self.reset_channels();
+            context.set_latency_samples(self.latency_samples());
+        }
+
+        let playing = context.transport().playing;
+        if playing && !self.playing && self.params.noise_reset.value() {
+            self.reset_noise();
+        }
+        self.playing = playing;
+        let limiter_enabled = self.params.limiter.value();
+        if limiter_enabled != self.limiter_enabled {
+            self.limiter_enabled = limiter_enabled;
+            self.limiter.reset();
+            context.set_latency_samples(self.latency_samples());
+        }
+        let stages = self.params.stages.value() as usize;
+        if stages != self.chain.stages {
+            self.chain.stages = stages;
+            context.set_latency_samples(self.latency_samples());
+        }
+
+        let stereo_mode = self.params.stereo_mode.value();
+        let dry_delay = self.shaper_latency();
+        let fade_step = self.fade_step();
+        let attack =
+            EnvelopeFollower::coefficient(self.sample_rate, self.params.envelope_attack.value());
+        let release =
+            EnvelopeFollower::coefficient(self.sample_rate, self.params.envelope_release.value());
+        let envelope_to_mix = self.params.envelope_to_mix.value();
+        let sidechain_attack =
+            EnvelopeFollower::coefficient(self.sample_rate, self.params.sidechain_attack.value());
+        let sidechain_release =
+            EnvelopeFollower::coefficient(self.sample_rate, self.params.sidechain_release.value());
+        let sidechain_target = self.params.sidechain_target.value();
+        let sidechain_listen = self.params.sidechain_listen.value();
+        let listen = self.params.listen.value();
+        let noise_settings = NoiseSettings {
+            color: self.params.noise_color.value(),
+            mode: self.params.noise_mode.value(),
+            amount: 0.0,
+            crackle_probability: NoiseSettings::crackle_probability(self.sample_rate),
+            correlation: 0.0,
+        };
+        let noise_follows_input = self.params.noise_follows_input.value();
+        // Holds on to the custom curve's table so the editor can't swap it out mid-buffer
+        let params = self.params.clone();
+        let custom = params.custom_table.read();
+        let sidechain = aux
+            .inputs
+            .first()
+            .map(|sidechain| sidechain.as_slice_immutable());
+        for (sample_idx, channel_samples) in buffer.iter_samples().enumerate() {
             let mut pre_amplitude = 0.0;
             let mut amplitude = 0.0;
             let num_samples = channel_samples.len();
 
-            for sample in channel_samples {
-                let unprocessed_sample = *sample;
-                pre_amplitude += *sample;
-                *sample *= 1.0 + self.rng.gen::<f32>() * MAX_NOISE_VOLUME * noise;
-                *sample *= drive;
-                *sample = algorithm.calculate(*sample);
-                *sample = *sample * mix + unprocessed_sample * (1.0 - mix);
-                amplitude += *sample;
-                *sample *= gain;
+            let mut samples = channel_samples.into_iter();
+            let (Some(left), Some(right)) = (samples.next(), samples.next()) else {
+                continue;
+            };
+            let envelope = self
+                .envelope
+                .process(left.abs().max(right.abs()), attack, release)
+                .min(1.0);
+            let modulation = self.params.envelope_depth.smoothed.next() * envelope;
+
+            let sidechain_samples = sidechain
+                .and_then(|channels| {
+                    Some([
+                        *channels.first()?.get(sample_idx)?,
+                        *channels.get(1)?.get(sample_idx)?,
+                    ])
+                })
+                .unwrap_or([0.0; 2]);
+            let sidechain_envelope = self
+                .sidechain_envelope
+                .process(
+                    sidechain_samples[0].abs().max(sidechain_samples[1].abs()),
+                    sidechain_attack,
+                    sidechain_release,
+                )
+                .min(1.0);
+            let sidechain_modulation =
+                self.params.sidechain_amount.smoothed.next() * sidechain_envelope;
+            let sidechain_modulation_for = |target: SidechainTarget| {
+                if target == sidechain_target {
+                    sidechain_modulation
+                } else {
+                    0.0
+                }
+            };
+
+            let drive_modulation = modulation + sidechain_modulation_for(SidechainTarget::Drive);
+            let mix_modulation = if envelope_to_mix { modulation } else { 0.0 }
+                + sidechain_modulation_for(SidechainTarget::Mix);
+            let modulate_mix = |mix: f32| (mix + mix_modulation).clamp(0.0, 1.0);
+
+            self.update_multiband(fade_step);
+            self.update_chain(fade_step);
+            self.update_parallel(fade_step);
+            self.update_dc_blocker();
+            self.update_tone();
+            self.update_analog();
+            self.update_feedback_filter();
+            let gain = self.params.gain.smoothed.next();
+            // Following the input keeps the noise from hissing while the input is silent
+            let noise_gate = if noise_follows_input { envelope } else { 1.0 };
+            let noise = (self.params.noise.smoothed.next()
+                + sidechain_modulation_for(SidechainTarget::Noise))
+            .clamp(0.0, 1.0)
+                * noise_gate;
+            let fades = &mut self.algorithm_fades;
+            let crusher = &self.params.crusher;
+            let settings_a = ChannelSettings {
+                drive: modulate_drive(self.params.drive.smoothed.next(), drive_modulation),
+                algorithm: fades
+                    .algorithm
+                    .next(self.params.algorithm.value(), fade_step),
+                morph_algorithm: fades
+                    .morph_algorithm
+                    .next(self.params.morph_algorithm.value(), fade_step),
+                morph: self.params.morph.smoothed.next(),
+                bias: self.params.bias.smoothed.next(),
+                mix: modulate_mix(self.params.mix.smoothed.next()),
+                noise: NoiseSettings {
+                    amount: noise,
+                    correlation: self.params.noise_correlation.smoothed.next(),
+                    ..noise_settings
+                },
+                crusher: CrusherSettings {
+                    enabled: crusher.enabled.value(),
+                    position: crusher.position.value(),
+                    bits: crusher.bits.smoothed.next(),
+                    downsample: crusher.downsample.smoothed.next(),
+                    jitter: crusher.jitter.smoothed.next(),
+                    dither: crusher.dither.value(),
+                },
+                oversampling,
+                anti_aliasing,
+                dc_block: self.params.dc_block.value(),
+                dc_block_coefficient: self.dc_block_coefficient,
+                context: AlgorithmContext {
+                    custom: &custom,
+                    fold: FoldSettings {
+                        folds: self.params.folds.smoothed.next(),
+                        symmetry: self.params.fold_symmetry.smoothed.next(),
+                    },
+                    analog: &self.analog,
+                },
+                feedback: FeedbackSettings {
+                    amount: self.params.feedback.smoothed.next(),
+                    delay: FeedbackSettings::delay_samples(
+                        self.sample_rate,
+                        self.params.feedback_delay.smoothed.next(),
+                    ),
+                    filter: &self.feedback_filter,
+                },
+                chain: &self.chain,
+                parallel: &self.parallel,
+                pre_filter: &self.pre_filter,
+                post_filter: &self.post_filter,
+            };
+            // The smoothers have to keep running even when the second channel's settings are unused
+            let drive_b = modulate_drive(
+                self.params.channel_b.drive.smoothed.next(),
+                drive_modulation,
+            );
+            let mix_b = modulate_mix(self.params.channel_b.mix.smoothed.next());
+            let algorithm_b = fades
+                .algorithm_b
+                .next(self.params.channel_b.algorithm.value(), fade_step);
+            let settings_b = if stereo_mode.is_split() {
+                ChannelSettings {
+                    drive: drive_b,
+                    algorithm: algorithm_b,
+                    mix: mix_b,
+                    ..settings_a
+                }
+            } else {
+                settings_a
+            };
+            self.modulated_values = ModulatedValues {
+                drive: settings_a.drive,
+                mix: settings_a.mix,
+                noise,
+                drive_b,
+                auto_gain: 1.0,
+            };
+
+            let unprocessed = [*left, *right];
+            let [a, b] = stereo_mode.encode(unprocessed);
+            // The noise is correlated between left and right, so in mid/side mode it's encoded
+            // just like the signal. Otherwise fully correlated noise would only end up on one side.
+            let [noise_a, noise_b] = stereo_mode
+                .encode([0, 1].map(|channel| self.channels[channel].next_noise(&settings_a.noise)));
+            let (main_a, parallel_a) =
+                self.channels[0].process(a, noise_a, &settings_a, &self.multiband);
+            let (main_b, parallel_b) =
+                self.channels[1].process(b, noise_b, &settings_b, &self.multiband);
+            // The limiter comes after the mix, so only the shapers' latency has to be matched
+            let delayed = [0, 1]
+                .map(|channel| self.dry_delays[channel].process(unprocessed[channel], dry_delay));
+            let [dry_a, dry_b] = stereo_mode.encode(delayed);
+            // The dry/wet mix happens before decoding so mid and side can have different mixes.
+            // Decoding is linear, so the paths can be decoded separately to pan them.
+            let dry = stereo_mode.decode([
+                dry_a * (1.0 - settings_a.mix),
+                dry_b * (1.0 - settings_b.mix),
+            ]);
+            let main = stereo_mode.decode([main_a * settings_a.mix, main_b * settings_b.mix]);
+            let parallel =
+                stereo_mode.decode([parallel_a * settings_a.mix, parallel_b * settings_b.mix]);
+            let wet = self.parallel.combine(main, parallel);
+            let mixed = [dry[0] + wet[0], dry[1] + wet[1]];
+            // The compensation keeps following the signal while it's disabled, so enabling it
+            // doesn't cause a jump
+            let compensation = self.auto_gain.process(unprocessed, mixed);
+            let compensation = if self.params.auto_gain.value() {
+                self.modulated_values.auto_gain = compensation;
+                compensation
+            } else {
+                1.0
+            };
+            let mixed = mixed.map(|sample| sample * compensation);
+            let wet = wet.map(|sample| sample * compensation);
+            // Listening to the sidechain replaces the output, but everything keeps running
+            let output = if sidechain_listen {
+                sidechain_samples
+            } else {
+                listen.output(mixed, delayed, wet)
+            };
+
+            for (mixed_sample, unprocessed_sample) in output.into_iter().zip(unprocessed) {
+                pre_amplitude += unprocessed_sample;
+                amplitude += mixed_sample;
             }
+            let output = output.map(|sample| sample * gain);
+            // The limiter's smoother keeps running while it's disabled
+            let limiter_ceiling = util::db_to_gain(self.params.limiter_ceiling.smoothed.next());
+            [*left, *right] = if limiter_enabled {
+                self.limiter.process(output, limiter_ceiling)
+            } else {
+                output
+            };
 
             // Handle peak meter values
             if self.params.editor_state.is_open() {
@@ -151,13 +526,220 @@ impl Plugin for Distortoise {
 }
 
 impl Distortoise {
+    /// The delay of the wet signal in samples, rounded to what the host can compensate for.
+    fn latency_samples(&self) -> u32 {
+        let limiter_latency = if self.limiter_enabled {
+            self.limiter.latency() as u32
+        } else {
+            0
+        };
+        self.shaper_latency().round() as u32 + limiter_latency
+    }
+
+    /// The delay of the shapers along the main path in samples. The dry signal is delayed by
+    /// exactly this much, while the host can only compensate for whole samples.
+    fn shaper_latency(&self) -> f32 {
+        // Every stage of the chain is oversampled separately
+        self.stage_latency() * self.chain.stages.max(1) as f32
+    }
+
+    /// The delay of a single shaper in samples, which doesn't have to be a whole number.
+    fn stage_latency(&self) -> f32 {
+        // The anti-aliasing runs at the oversampled rate
+        self.oversampling.latency()
+            + self.anti_aliasing.latency() / self.oversampling.factor() as f32
+    }
+
+    fn reset_channels(&mut self) {
+        for channel in &mut self.channels {
+            channel.reset();
+        }
+        for delay in &mut self.dry_delays {
+            delay.reset();
+        }
+    }
+
+    fn reset_noise(&mut self) {
+        for channel in &mut self.channels {
+            channel.reset_noise();
+        }
+    }
+
+    /// How far the algorithm crossfades move per sample.
+    fn fade_step(&self) -> f32 {
+        let fade_samples = self.params.algorithm_crossfade.value() / 1000.0 * self.sample_rate;
+        if fade_samples < 1.0 {
+            1.0
+        } else {
+            fade_samples.recip()
+        }
+    }
+
+    /// Advances the band parameters' smoothers and updates the crossover coefficients when needed.
+    fn update_multiband(&mut self, fade_step: f32) {
+        let params = &self.params;
+        let multiband = &mut self.multiband;
+        multiband.bands = params.bands.value() as usize;
+        multiband.dry_delay = self.stage_latency();
+
+        let any_solo = params
+            .band_params
+            .iter()
+            .take(multiband.bands)
+            .any(|band| band.solo.value());
+        for ((settings, band), fade) in multiband
+            .band_settings
+            .iter_mut()
+            .zip(&params.band_params)
+            .zip(&mut self.algorithm_fades.bands)
+        {
+            *settings = BandSettings {
+                algorithm: fade.next(band.algorithm.value(), fade_step),
+                drive: band.drive.smoothed.next(),
+                mix: band.mix.smoothed.next(),
+                gain: band.gain.smoothed.next(),
+                audible: !band.mute.value() && (!any_solo || band.solo.value()),
+                bypass: band.bypass.value(),
+            };
+        }
+
+        let mut lower_frequency = 0.0f32;
+        for ((coefficients, current_frequency), crossover) in multiband
+            .crossover
+            .splits
+            .iter_mut()
+            .zip(&mut self.crossover_frequencies)
+            .zip(&params.crossovers)
+        {
+            // The splits aren't allowed to cross each other
+            let frequency = crossover.frequency.smoothed.next().max(lower_frequency);
+            lower_frequency = frequency;
+            if frequency != *current_frequency {
+                *current_frequency = frequency;
+                *coefficients = SplitCoefficients::new(self.sample_rate, frequency);
+            }
+        }
+    }
+
+    fn update_chain(&mut self, fade_step: f32) {
+        self.chain.order = self.params.stage_order.value();
+        for ((settings, stage), fade) in self
+            .chain
+            .stage_settings
+            .iter_mut()
+            .zip(&self.params.stage_params)
+            .zip(&mut self.algorithm_fades.stages)
+        {
+            settings.algorithm = fade.next(stage.algorithm.value(), fade_step);
+            settings.drive = stage.drive.smoothed.next();
+            settings.filter.update(
+                self.sample_rate,
+                stage.highpass.smoothed.next(),
+                stage.lowpass.smoothed.next(),
+                0.0,
+            );
+        }
+    }
+
+    fn update_parallel(&mut self, fade_step: f32) {
+        let params = &self.params.parallel;
+        let parallel = &mut self.parallel;
+        parallel.algorithm = self
+            .algorithm_fades
+            .parallel
+            .next(params.algorithm.value(), fade_step);
+        parallel.drive = params.drive.smoothed.next();
+        parallel.filter.update(
+            self.sample_rate,
+            params.highpass.smoothed.next(),
+            params.lowpass.smoothed.next(),
+            0.0,
+        );
+        parallel.balance = params.balance.smoothed.next();
+        parallel.main_pan = params.main_pan.smoothed.next();
+        parallel.parallel_pan = params.pan.smoothed.next();
+        // The parallel path has a single shaper, so it waits for the rest of the main path's chain
+        parallel.delay = self.stage_latency() * self.chain.stages.saturating_sub(1) as f32;
+    }
+
+    fn update_dc_blocker(&mut self) {
+        let frequency = self.params.dc_block_frequency.smoothed.next();
+        if frequency != self.dc_block_frequency {
+            self.dc_block_frequency = frequency;
+            self.dc_block_coefficient = DcBlocker::coefficient(self.sample_rate, frequency);
+        }
+    }
+
+    fn update_tone(&mut self) {
+        let pre = &self.params.pre_filter;
+        let post = &self.params.post_filter;
+        let pre_tilt = pre.tilt.smoothed.next();
+        self.pre_filter.update(
+            self.sample_rate,
+            pre.highpass.smoothed.next(),
+            pre.lowpass.smoothed.next(),
+            pre_tilt,
+        );
+        // De-emphasis adds the opposite of the pre-filter's tilt to the post-filter
+        let de_emphasis = if self.params.de_emphasis.value() {
+            pre_tilt
+        } else {
+            0.0
+        };
+        self.post_filter.update(
+            self.sample_rate,
+            post.highpass.smoothed.next(),
+            post.lowpass.smoothed.next(),
+            post.tilt.smoothed.next() - de_emphasis,
+        );
+    }
+
+    fn update_analog(&mut self) {
+        // The models run at the oversampled rate
+        self.analog.update(
+            self.sample_rate * self.oversampling.factor() as f32,
+            self.params.tube_grid_current.smoothed.next(),
+            self.params.tube_recovery.smoothed.next(),
+            self.params.tape_hysteresis.smoothed.next(),
+            self.params.tape_emphasis.smoothed.next(),
+        );
+    }
+
+    fn update_feedback_filter(&mut self) {
+        // The damping is the lowpass
+        self.feedback_filter.update(
+            self.sample_rate,
+            FEEDBACK_HIGHPASS,
+            self.params.feedback_damping.smoothed.next(),
+            0.0,
+        );
+    }
+
     fn update_ui_data(&mut self) {
         let ui_data = self.ui_data.lock().unwrap();
-        ui_data.set_drive(self.params.drive.smoothed.next());
+        ui_data.set_drive(self.modulated_values.drive);
+        ui_data.set_mix(self.modulated_values.mix);
+        ui_data.set_noise(self.modulated_values.noise);
         ui_data.set_algorithm(self.params.algorithm.value());
+        ui_data.set_morph_algorithm(self.params.morph_algorithm.value());
+        ui_data.set_morph(self.params.morph.smoothed.previous());
+        ui_data.set_bias(self.params.bias.smoothed.previous());
+        ui_data.set_split(self.params.stereo_mode.value().is_split());
+        ui_data.set_drive_b(self.modulated_values.drive_b);
+        ui_data.set_auto_gain(util::gain_to_db(self.modulated_values.auto_gain));
+        ui_data.set_limiter_gain(if self.limiter_enabled {
+            util::gain_to_db(self.limiter.gain())
+        } else {
+            0.0
+        });
+        ui_data.set_algorithm_b(self.params.channel_b.algorithm.value());
     }
 }
 
+fn modulate_drive(drive: f32, modulation: f32) -> f32 {
+    drive * MAX_ENVELOPE_DRIVE.powf(modulation)
+}
+
 impl ClapPlugin for Distortoise {
     const CLAP_ID: &'static str = "com.your-domain.distortoise";
     const CLAP_DESCRIPTION: Option<&'static str> = Some("Add torsion to dis");
@@ -1,23 +1,281 @@
 use nih_plug::{prelude::*, util::MINUS_INFINITY_DB};
 use nih_plug_vizia::ViziaState;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
-use crate::{algorithms::DistortionAlgorithm, editor};
+use crate::{
+    algorithms::{
+        custom::{CustomCurve, CustomTable},
+        DistortionAlgorithm,
+    },
+    dsp::{
+        adaa::AntiAliasing,
+        chain::{StageOrder, MAX_CHAIN_STAGES, MAX_EXTRA_STAGES},
+        crossover::{MAX_BANDS, MAX_SPLITS},
+        crusher::CrusherPosition,
+        envelope::SidechainTarget,
+        feedback::MAX_FEEDBACK_DELAY_MS,
+        listen::ListenMode,
+        noise::{NoiseColor, NoiseMode},
+        oversampling::Oversampling,
+        stereo::StereoMode,
+        tone::{HIGHPASS_OFF, LOWPASS_OFF},
+    },
+    editor,
+};
 
 #[derive(Params)]
 pub struct DistortoiseParams {
     #[persist = "editor-state"]
     pub editor_state: Arc<ViziaState>,
+    /// The curve for [`DistortionAlgorithm::Custom`]. The editor changes this directly, and has to
+    /// update [`custom_table`][Self::custom_table] afterwards.
+    #[persist = "custom-curve"]
+    pub custom_curve: Arc<RwLock<CustomCurve>>,
+    /// The lookup table the audio thread uses for the custom curve.
+    pub custom_table: CustomTable,
+    /// Flipped by the editor whenever it edits the custom curve. The curve isn't a parameter, so
+    /// hosts wouldn't notice that there's something to save otherwise.
+    #[id = "custom_curve_edited"]
+    pub custom_curve_edited: BoolParam,
     #[id = "algorithm"]
     pub algorithm: EnumParam<DistortionAlgorithm>,
+    /// The algorithm [`morph`][Self::morph] blends towards.
+    #[id = "morph_algorithm"]
+    pub morph_algorithm: EnumParam<DistortionAlgorithm>,
+    #[id = "morph"]
+    pub morph: FloatParam,
+    /// How long switching between algorithms takes, in milliseconds.
+    #[id = "algorithm_crossfade"]
+    pub algorithm_crossfade: FloatParam,
     #[id = "drive"]
     pub drive: FloatParam,
+    /// How much the input's envelope modulates the drive. Negative values lower the drive on
+    /// transients.
+    #[id = "envelope_depth"]
+    pub envelope_depth: FloatParam,
+    #[id = "envelope_attack"]
+    pub envelope_attack: FloatParam,
+    #[id = "envelope_release"]
+    pub envelope_release: FloatParam,
+    /// Whether the envelope modulates the mix as well.
+    #[id = "envelope_to_mix"]
+    pub envelope_to_mix: BoolParam,
+    /// What the sidechain input's envelope modulates.
+    #[id = "sidechain_target"]
+    pub sidechain_target: EnumParam<SidechainTarget>,
+    /// How much the sidechain modulates its target. Negative values turn the target down when the
+    /// sidechain is loud.
+    #[id = "sidechain_amount"]
+    pub sidechain_amount: FloatParam,
+    #[id = "sidechain_attack"]
+    pub sidechain_attack: FloatParam,
+    #[id = "sidechain_release"]
+    pub sidechain_release: FloatParam,
+    /// Outputs the sidechain input instead of the processed signal.
+    #[id = "sidechain_listen"]
+    pub sidechain_listen: BoolParam,
+    /// The gain going into the wavefolders.
+    #[id = "folds"]
+    pub folds: FloatParam,
+    /// Folds the positive and negative halves of the signal by different amounts.
+    #[id = "fold_symmetry"]
+    pub fold_symmetry: FloatParam,
+    /// How much grid current shifts the tube's bias when it's driven hard.
+    #[id = "tube_grid_current"]
+    pub tube_grid_current: FloatParam,
+    /// How long the tube's bias takes to recover, in milliseconds.
+    #[id = "tube_recovery"]
+    pub tube_recovery: FloatParam,
+    /// The width of the tape's hysteresis loop.
+    #[id = "tape_hysteresis"]
+    pub tape_hysteresis: FloatParam,
+    /// How much sooner the highs saturate on tape than the lows, in decibels.
+    #[id = "tape_emphasis"]
+    pub tape_emphasis: FloatParam,
+    /// Shifts the signal before shaping, which adds even harmonics.
+    #[id = "bias"]
+    pub bias: FloatParam,
+    /// Matches the output's loudness to the input's before the gain is applied.
+    #[id = "auto_gain"]
+    pub auto_gain: BoolParam,
     #[id = "gain"]
     pub gain: FloatParam,
+    /// Keeps the output's true peak below the ceiling. This adds latency.
+    #[id = "limiter"]
+    pub limiter: BoolParam,
+    /// The limiter's maximum output level in decibels.
+    #[id = "limiter_ceiling"]
+    pub limiter_ceiling: FloatParam,
+    /// Outputs the difference the processing makes or only the wet signal, before the gain.
+    #[id = "listen"]
+    pub listen: EnumParam<ListenMode>,
     #[id = "noise"]
     pub noise: FloatParam,
+    #[id = "noise_color"]
+    pub noise_color: EnumParam<NoiseColor>,
+    #[id = "noise_mode"]
+    pub noise_mode: EnumParam<NoiseMode>,
+    /// Scales the noise by the input's envelope, so it doesn't hiss during silence.
+    #[id = "noise_follows_input"]
+    pub noise_follows_input: BoolParam,
+    /// How similar the two channels' noise is.
+    #[id = "noise_correlation"]
+    pub noise_correlation: FloatParam,
+    /// Restarts the noise when the host resets the plugin or starts playback, so renders are
+    /// identical.
+    #[id = "noise_reset"]
+    pub noise_reset: BoolParam,
     #[id = "mix"]
     pub mix: FloatParam,
+    #[id = "oversampling"]
+    pub oversampling: EnumParam<Oversampling>,
+    #[id = "anti_aliasing"]
+    pub anti_aliasing: EnumParam<AntiAliasing>,
+    #[id = "stereo_mode"]
+    pub stereo_mode: EnumParam<StereoMode>,
+    #[nested(id_prefix = "b", group = "Right / Side")]
+    pub channel_b: ChannelParams,
+    /// The number of bands. With a single band the multiband processing is disabled.
+    #[id = "bands"]
+    pub bands: IntParam,
+    #[nested(array, group = "Band")]
+    pub band_params: [BandParams; MAX_BANDS],
+    #[nested(array, group = "Crossover")]
+    pub crossovers: [CrossoverParams; MAX_SPLITS],
+    /// The number of shaping stages in series. The main algorithm is the first one.
+    #[id = "stages"]
+    pub stages: IntParam,
+    /// The order the stages after the main algorithm run in.
+    #[id = "stage_order"]
+    pub stage_order: EnumParam<StageOrder>,
+    /// The stages after the main algorithm, numbered from two.
+    #[nested(array, group = "Stage")]
+    pub stage_params: [StageParams; MAX_EXTRA_STAGES],
+    /// A second distortion path next to the main one.
+    #[nested(id_prefix = "parallel", group = "Parallel")]
+    pub parallel: ParallelParams,
+    /// Removes the DC offset that asymmetric algorithms and the noise add to the shaped signal.
+    #[id = "dc_block"]
+    pub dc_block: BoolParam,
+    #[id = "dc_block_frequency"]
+    pub dc_block_frequency: FloatParam,
+    /// Filters the signal before it's shaped.
+    #[nested(id_prefix = "pre", group = "Pre-Filter")]
+    pub pre_filter: ToneParams,
+    /// Filters the shaped signal.
+    #[nested(id_prefix = "post", group = "Post-Filter")]
+    pub post_filter: ToneParams,
+    #[nested(id_prefix = "crusher", group = "Bitcrusher")]
+    pub crusher: CrusherParams,
+    /// How much of the shaped signal is fed back into the algorithm. Negative values invert it.
+    #[id = "feedback"]
+    pub feedback: FloatParam,
+    #[id = "feedback_delay"]
+    pub feedback_delay: FloatParam,
+    /// A lowpass in the feedback path.
+    #[id = "feedback_damping"]
+    pub feedback_damping: FloatParam,
+    /// Undoes the pre-filter's tilt after shaping, so the tilt only changes how the signal distorts.
+    #[id = "de_emphasis"]
+    pub de_emphasis: BoolParam,
+}
+
+/// The settings for the right or side channel when the stereo mode isn't linked. The left or mid
+/// channel uses the main settings.
+#[derive(Params)]
+pub struct ChannelParams {
+    #[id = "algorithm"]
+    pub algorithm: EnumParam<DistortionAlgorithm>,
+    #[id = "drive"]
+    pub drive: FloatParam,
+    #[id = "mix"]
+    pub mix: FloatParam,
+}
+
+#[derive(Params)]
+pub struct BandParams {
+    #[id = "band_algorithm"]
+    pub algorithm: EnumParam<DistortionAlgorithm>,
+    /// Multiplied with the main drive.
+    #[id = "band_drive"]
+    pub drive: FloatParam,
+    #[id = "band_mix"]
+    pub mix: FloatParam,
+    #[id = "band_gain"]
+    pub gain: FloatParam,
+    #[id = "band_solo"]
+    pub solo: BoolParam,
+    #[id = "band_mute"]
+    pub mute: BoolParam,
+    #[id = "band_bypass"]
+    pub bypass: BoolParam,
+}
+
+#[derive(Params)]
+pub struct StageParams {
+    #[id = "stage_algorithm"]
+    pub algorithm: EnumParam<DistortionAlgorithm>,
+    #[id = "stage_drive"]
+    pub drive: FloatParam,
+    /// Filters the signal coming from the previous stage.
+    #[id = "stage_highpass"]
+    pub highpass: FloatParam,
+    #[id = "stage_lowpass"]
+    pub lowpass: FloatParam,
+}
+
+#[derive(Params)]
+pub struct ToneParams {
+    #[id = "highpass"]
+    pub highpass: FloatParam,
+    #[id = "lowpass"]
+    pub lowpass: FloatParam,
+    /// The difference in decibels between the highs and the lows.
+    #[id = "tilt"]
+    pub tilt: FloatParam,
+}
+
+#[derive(Params)]
+pub struct CrusherParams {
+    #[id = "enabled"]
+    pub enabled: BoolParam,
+    #[id = "position"]
+    pub position: EnumParam<CrusherPosition>,
+    #[id = "bits"]
+    pub bits: FloatParam,
+    /// The factor the sample rate is reduced by.
+    #[id = "downsample"]
+    pub downsample: FloatParam,
+    /// Randomizes the time between held samples.
+    #[id = "jitter"]
+    pub jitter: FloatParam,
+    #[id = "dither"]
+    pub dither: BoolParam,
+}
+
+#[derive(Params)]
+pub struct ParallelParams {
+    /// From only the main path to only the parallel path.
+    #[id = "balance"]
+    pub balance: FloatParam,
+    #[id = "algorithm"]
+    pub algorithm: EnumParam<DistortionAlgorithm>,
+    #[id = "drive"]
+    pub drive: FloatParam,
+    #[id = "highpass"]
+    pub highpass: FloatParam,
+    #[id = "lowpass"]
+    pub lowpass: FloatParam,
+    #[id = "main_pan"]
+    pub main_pan: FloatParam,
+    #[id = "pan"]
+    pub pan: FloatParam,
+}
+
+#[derive(Params)]
+pub struct CrossoverParams {
+    #[id = "crossover"]
+    pub frequency: FloatParam,
 }
 
 impl Default for DistortoiseParams {
@@ -25,19 +283,138 @@ impl Default for DistortoiseParams {
         Self {
             editor_state: editor::default_state(),
 
-            algorithm: EnumParam::new("Algorithm", DistortionAlgorithm::SoftClip),
+            custom_curve: Arc::new(RwLock::new(CustomCurve::default())),
 
-            drive: FloatParam::new(
-                "Drive",
-                1.0,
+            custom_table: CustomTable::default(),
+
+            custom_curve_edited: BoolParam::new("Custom Curve Edited", false)
+                .hide()
+                .non_automatable(),
+
+            algorithm: EnumParam::new("Algorithm A", DistortionAlgorithm::SoftClip),
+
+            morph_algorithm: EnumParam::new("Algorithm B", DistortionAlgorithm::SoftClip),
+
+            morph: FloatParam::new("Morph", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_smoother(SmoothingStyle::Linear(50.0))
+                .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            algorithm_crossfade: FloatParam::new(
+                "Algorithm Crossfade",
+                20.0,
+                FloatRange::Skewed {
+                    min: 0.0,
+                    max: 200.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_unit(" ms")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            drive: drive_param("Drive"),
+
+            envelope_depth: FloatParam::new(
+                "Envelope",
+                0.0,
                 FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            envelope_attack: time_param("Envelope Attack", 5.0, 0.1, 100.0),
+
+            envelope_release: time_param("Envelope Release", 150.0, 5.0, 1000.0),
+
+            envelope_to_mix: BoolParam::new("Envelope to Mix", false),
+
+            sidechain_target: EnumParam::new("Sidechain Target", SidechainTarget::Drive),
+
+            sidechain_amount: FloatParam::new(
+                "Sidechain Amount",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            sidechain_attack: time_param("Sidechain Attack", 5.0, 0.1, 100.0),
+
+            sidechain_release: time_param("Sidechain Release", 150.0, 5.0, 1000.0),
+
+            sidechain_listen: BoolParam::new("Sidechain Listen", false),
+
+            folds: FloatParam::new(
+                "Folds",
+                2.0,
+                FloatRange::Skewed {
                     min: 1.0,
-                    max: 10.0,
+                    max: 8.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            fold_symmetry: FloatParam::new(
+                "Fold Symmetry",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            tube_grid_current: FloatParam::new(
+                "Tube Grid Current",
+                0.5,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            tube_recovery: time_param("Tube Recovery", 50.0, 1.0, 500.0),
+
+            tape_hysteresis: FloatParam::new(
+                "Tape Hysteresis",
+                0.3,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            tape_emphasis: FloatParam::new(
+                "Tape Emphasis",
+                6.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 12.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            bias: FloatParam::new(
+                "Bias",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
                 },
             )
             .with_smoother(SmoothingStyle::Linear(50.0))
             .with_value_to_string(formatters::v2s_f32_rounded(2)),
 
+            auto_gain: BoolParam::new("Auto Gain", false),
+
             gain: FloatParam::new(
                 "Gain",
                 util::db_to_gain(0.0),
@@ -52,12 +429,343 @@ impl Default for DistortoiseParams {
             .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
             .with_string_to_value(formatters::s2v_f32_gain_to_db()),
 
+            limiter: BoolParam::new("Limiter", false),
+
+            limiter_ceiling: FloatParam::new(
+                "Limiter Ceiling",
+                -1.0,
+                FloatRange::Linear {
+                    min: -24.0,
+                    max: 0.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            listen: EnumParam::new("Listen", ListenMode::Off),
+
             noise: FloatParam::new("Noise", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
                 .with_smoother(SmoothingStyle::Linear(50.0)),
 
-            mix: FloatParam::new("Mix", 1.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+            noise_color: EnumParam::new("Noise Color", NoiseColor::White),
+
+            noise_mode: EnumParam::new("Noise Mode", NoiseMode::Multiplicative),
+
+            noise_follows_input: BoolParam::new("Noise Follows Input", false),
+
+            noise_correlation: FloatParam::new(
+                "Noise Stereo Correlation",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            noise_reset: BoolParam::new("Reset Noise on Playback", false),
+
+            mix: mix_param("Mix"),
+
+            oversampling: EnumParam::new("Oversampling", Oversampling::X1),
+
+            anti_aliasing: EnumParam::new("Anti-Aliasing", AntiAliasing::Off),
+
+            stereo_mode: EnumParam::new("Stereo Mode", StereoMode::Linked),
+
+            channel_b: ChannelParams::default(),
+
+            bands: IntParam::new(
+                "Bands",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_BANDS as i32,
+                },
+            ),
+
+            band_params: std::array::from_fn(|index| BandParams::new(index + 1)),
+
+            crossovers: [(1, 200.0), (2, 1000.0), (3, 5000.0)]
+                .map(|(number, frequency)| CrossoverParams::new(number, frequency)),
+
+            stages: IntParam::new(
+                "Stages",
+                1,
+                IntRange::Linear {
+                    min: 1,
+                    max: MAX_CHAIN_STAGES as i32,
+                },
+            ),
+
+            stage_order: EnumParam::new("Stage Order", StageOrder::TwoThreeFour),
+
+            stage_params: std::array::from_fn(|index| StageParams::new(index + 2)),
+
+            parallel: ParallelParams::default(),
+
+            dc_block: BoolParam::new("DC Blocker", true),
+
+            dc_block_frequency: FloatParam::new(
+                "DC Blocker Frequency",
+                10.0,
+                FloatRange::Skewed {
+                    min: 5.0,
+                    max: 50.0,
+                    factor: FloatRange::skew_factor(-1.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_unit(" Hz")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+
+            pre_filter: ToneParams::new("Pre"),
+
+            post_filter: ToneParams::new("Post"),
+
+            crusher: CrusherParams::default(),
+
+            feedback: FloatParam::new(
+                "Feedback",
+                0.0,
+                FloatRange::Linear {
+                    min: -1.0,
+                    max: 1.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+
+            feedback_delay: time_param("Feedback Delay", 2.0, 0.1, MAX_FEEDBACK_DELAY_MS)
+                .with_smoother(SmoothingStyle::Logarithmic(50.0)),
+
+            feedback_damping: filter_frequency_param("Feedback Damping", LOWPASS_OFF),
+
+            de_emphasis: BoolParam::new("De-Emphasis", false),
+        }
+    }
+}
+
+impl Default for ChannelParams {
+    fn default() -> Self {
+        Self {
+            algorithm: EnumParam::new("Right/Side Algorithm", DistortionAlgorithm::SoftClip),
+            drive: drive_param("Right/Side Drive"),
+            mix: mix_param("Right/Side Mix"),
+        }
+    }
+}
+
+impl BandParams {
+    /// Bands are numbered from one, starting with the lowest.
+    fn new(number: usize) -> Self {
+        Self {
+            algorithm: EnumParam::new(
+                format!("Band {number} Algorithm"),
+                DistortionAlgorithm::SoftClip,
+            ),
+            drive: band_gain_param(&format!("Band {number} Drive")),
+            mix: mix_param(&format!("Band {number} Mix")),
+            gain: band_gain_param(&format!("Band {number} Gain")),
+            solo: BoolParam::new(format!("Band {number} Solo"), false),
+            mute: BoolParam::new(format!("Band {number} Mute"), false),
+            bypass: BoolParam::new(format!("Band {number} Bypass"), false),
+        }
+    }
+}
+
+impl StageParams {
+    /// The main algorithm is stage one, so these start at two.
+    fn new(number: usize) -> Self {
+        Self {
+            algorithm: EnumParam::new(
+                format!("Stage {number} Algorithm"),
+                DistortionAlgorithm::SoftClip,
+            ),
+            drive: drive_param(&format!("Stage {number} Drive")),
+            highpass: filter_frequency_param(&format!("Stage {number} Highpass"), HIGHPASS_OFF),
+            lowpass: filter_frequency_param(&format!("Stage {number} Lowpass"), LOWPASS_OFF),
+        }
+    }
+}
+
+impl ToneParams {
+    fn new(prefix: &str) -> Self {
+        Self {
+            highpass: filter_frequency_param(&format!("{prefix} Highpass"), HIGHPASS_OFF),
+            lowpass: filter_frequency_param(&format!("{prefix} Lowpass"), LOWPASS_OFF),
+            tilt: FloatParam::new(
+                format!("{prefix} Tilt"),
+                0.0,
+                FloatRange::Linear {
+                    min: -12.0,
+                    max: 12.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit(" dB")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+        }
+    }
+}
+
+impl Default for CrusherParams {
+    fn default() -> Self {
+        Self {
+            enabled: BoolParam::new("Bitcrusher", false),
+            position: EnumParam::new("Bitcrusher Position", CrusherPosition::Post),
+            bits: FloatParam::new(
+                "Bit Depth",
+                8.0,
+                FloatRange::Linear {
+                    min: 1.0,
+                    max: 16.0,
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit(" bits")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            downsample: FloatParam::new(
+                "Downsample",
+                1.0,
+                FloatRange::Skewed {
+                    min: 1.0,
+                    max: 32.0,
+                    factor: FloatRange::skew_factor(-1.5),
+                },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_unit("x")
+            .with_value_to_string(formatters::v2s_f32_rounded(1)),
+            jitter: FloatParam::new("Jitter", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
                 .with_smoother(SmoothingStyle::Linear(50.0))
                 .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            dither: BoolParam::new("Dither", false),
+        }
+    }
+}
+
+impl Default for ParallelParams {
+    fn default() -> Self {
+        Self {
+            balance: FloatParam::new(
+                "Parallel Balance",
+                0.0,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            )
+            .with_smoother(SmoothingStyle::Linear(50.0))
+            .with_value_to_string(formatters::v2s_f32_rounded(2)),
+            algorithm: EnumParam::new("Parallel Algorithm", DistortionAlgorithm::SoftClip),
+            drive: drive_param("Parallel Drive"),
+            highpass: filter_frequency_param("Parallel Highpass", HIGHPASS_OFF),
+            lowpass: filter_frequency_param("Parallel Lowpass", LOWPASS_OFF),
+            main_pan: pan_param("Main Pan"),
+            pan: pan_param("Parallel Pan"),
+        }
+    }
+}
+
+impl CrossoverParams {
+    fn new(number: usize, frequency: f32) -> Self {
+        Self {
+            frequency: FloatParam::new(
+                format!("Crossover {number}"),
+                frequency,
+                FloatRange::Skewed {
+                    min: 20.0,
+                    max: 20_000.0,
+                    factor: FloatRange::skew_factor(-2.0),
+                },
+            )
+            .with_smoother(SmoothingStyle::Logarithmic(50.0))
+            .with_value_to_string(formatters::v2s_f32_hz_then_khz(0))
+            .with_string_to_value(formatters::s2v_f32_hz_then_khz()),
         }
     }
 }
+
+/// The filter is switched off when the frequency is at the end of the range it starts at.
+fn filter_frequency_param(name: &str, off_frequency: f32) -> FloatParam {
+    let value_to_string = formatters::v2s_f32_hz_then_khz(0);
+    FloatParam::new(
+        name,
+        off_frequency,
+        FloatRange::Skewed {
+            min: HIGHPASS_OFF,
+            max: LOWPASS_OFF,
+            factor: FloatRange::skew_factor(-2.0),
+        },
+    )
+    .with_smoother(SmoothingStyle::Logarithmic(50.0))
+    .with_value_to_string(Arc::new(move |value| {
+        if value == off_frequency {
+            String::from("Off")
+        } else {
+            value_to_string(value)
+        }
+    }))
+    .with_string_to_value(formatters::s2v_f32_hz_then_khz())
+}
+
+/// A time in milliseconds.
+fn time_param(name: &str, default: f32, min: f32, max: f32) -> FloatParam {
+    FloatParam::new(
+        name,
+        default,
+        FloatRange::Skewed {
+            min,
+            max,
+            factor: FloatRange::skew_factor(-1.0),
+        },
+    )
+    .with_unit(" ms")
+    .with_value_to_string(formatters::v2s_f32_rounded(1))
+}
+
+fn drive_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        1.0,
+        FloatRange::Linear {
+            min: 1.0,
+            max: 10.0,
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(50.0))
+    .with_value_to_string(formatters::v2s_f32_rounded(2))
+}
+
+fn mix_param(name: &str) -> FloatParam {
+    FloatParam::new(name, 1.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+        .with_smoother(SmoothingStyle::Linear(50.0))
+        .with_value_to_string(formatters::v2s_f32_rounded(2))
+}
+
+fn pan_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        0.0,
+        FloatRange::Linear {
+            min: -1.0,
+            max: 1.0,
+        },
+    )
+    .with_smoother(SmoothingStyle::Linear(50.0))
+    .with_value_to_string(formatters::v2s_f32_panning())
+    .with_string_to_value(formatters::s2v_f32_panning())
+}
+
+fn band_gain_param(name: &str) -> FloatParam {
+    FloatParam::new(
+        name,
+        util::db_to_gain(0.0),
+        FloatRange::Skewed {
+            min: util::db_to_gain(-20.0),
+            max: util::db_to_gain(20.0),
+            factor: FloatRange::gain_skew_factor(-20.0, 20.0),
+        },
+    )
+    .with_smoother(SmoothingStyle::Logarithmic(50.0))
+    .with_unit(" dB")
+    .with_value_to_string(formatters::v2s_f32_gain_to_db(2))
+    .with_string_to_value(formatters::s2v_f32_gain_to_db())
+}